@@ -1,29 +1,45 @@
 use crate::{
     GameState,
+    bind::{TileCanvas, TileCanvases},
     enemy::Enemy,
-    equipment::Health,
+    input::{self, ArenaInput, Confirm, NavigateDown, NavigateUp, SelectOption},
+    layout::Widget,
+    level::Experience,
+    panel::{self, Modal, PanelStack},
     player::Player,
-    tile::{CollisionEvent, TextAnchor, text_tiles},
+    stats::{self, Health, Modifier, Modifiers, Stat, Stats, StatusEffectOf},
+    tile::{CollisionEvent, TextAnchor},
 };
-use bevy::{input::keyboard::KeyboardInput, prelude::*};
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 pub fn plugin(app: &mut App) {
     app.add_sub_state::<BattleState>()
+        .init_resource::<ArenaMenu>()
         .add_systems(OnEnter(GameState::Arena), enter_arena)
-        .add_systems(Update, player_stage.run_if(in_state(BattleState::Player)))
-        .add_systems(OnExit(GameState::Arena), exit_arena)
+        .add_systems(OnEnter(BattleState::Player), Blocking::expire)
+        .add_systems(OnExit(GameState::Arena), (exit_arena, Blocking::expire))
         .add_systems(
             Update,
             (
                 evaluate_win.run_if(in_state(BattleState::PlayerResult)),
                 evaluate_loss.run_if(in_state(BattleState::EnemyResult)),
+                redraw_arena.run_if(any_with_component::<ArenaWindow>),
             ),
         )
+        .add_systems(PostUpdate, activate_arena_input)
         .add_systems(
             OnEnter(BattleState::Complete(BattleComplete::Win)),
-            |mut commands: Commands| {
+            |mut commands: Commands, player: Single<&Experience, With<Player>>| {
                 info!("player won!");
-                commands.set_state(GameState::Overworld);
+                if player.pending > 0 {
+                    commands.set_state(GameState::LevelUp);
+                } else {
+                    commands.set_state(GameState::Overworld);
+                }
             },
         )
         .add_systems(
@@ -32,6 +48,11 @@ pub fn plugin(app: &mut App) {
                 panic!("player lost!");
             },
         )
+        .add_observer(navigate_arena::<NavigateUp>(-1))
+        .add_observer(navigate_arena::<NavigateDown>(1))
+        .add_observer(confirm_option)
+        .add_observer(select_option)
+        .add_observer(player_stage)
         .add_observer(enter_battle)
         .add_observer(observe_death);
 }
@@ -71,7 +92,7 @@ fn enter_battle(
 
 #[derive(Component)]
 #[component(immutable)]
-pub struct BattleTarget(usize);
+pub struct BattleTarget(pub usize);
 
 #[derive(EntityEvent)]
 pub struct Attack {
@@ -85,7 +106,7 @@ pub struct Death {
     pub entity: Entity,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, SubStates)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, SubStates, Serialize, Deserialize)]
 #[source(GameState = GameState::Arena)]
 pub enum BattleState {
     #[default]
@@ -95,86 +116,201 @@ pub enum BattleState {
     Complete(BattleComplete),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BattleComplete {
     Win,
     Loss,
 }
 
+/// The [`Panel`](crate::panel::Panel) that shows the fight.
 #[derive(Component)]
-pub struct ArenaEntity;
-
-const ARENA: &str = r#"
-+-------------------------------+
-|                               |
-|           F I G H T           |
-|                               |
-+-------------------------------+
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|      p                 e      |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-+-------------------------------+
-|                               |
-|  1. ATTACK        2. BLOCK    |
-|                               |
-|  3. ESCAPE                    |
-|                               |
-+-------------------------------+
-"#;
-
-fn enter_arena(mut commands: Commands) {
-    for (tile, position) in text_tiles(ARENA, 0, 0, TextAnchor::Center) {
-        commands.spawn((
-            tile,
-            Transform::from_translation(position.extend(10.0)),
-            ArenaEntity,
-        ));
+#[require(Modal, TileCanvas)]
+pub struct ArenaWindow;
+
+/// Width of the arena panel in tiles.
+const ARENA_WIDTH: u32 = 33;
+
+/// The choices of the player turn, in the order of [`SelectOption`].
+const OPTIONS: [&str; 3] = ["1. ATTACK", "2. BLOCK", "3. ESCAPE"];
+
+/// The highlighted option of the fight menu.
+#[derive(Resource, Default)]
+pub struct ArenaMenu {
+    pub selected: usize,
+}
+
+/// Picks an option of the fight menu by its index in [`OPTIONS`].
+#[derive(Event)]
+struct ChooseOption {
+    index: usize,
+}
+
+fn arena_panel(selected: usize) -> Widget<'static> {
+    Widget::border(Widget::column([
+        Widget::Space(1),
+        Widget::centered("F I G H T"),
+        Widget::Space(1),
+        Widget::Separator,
+        Widget::Space(6),
+        Widget::row([Widget::centered("p"), Widget::centered("e")]),
+        Widget::Space(6),
+        Widget::Separator,
+        Widget::Space(1),
+        Widget::list(OPTIONS, Some(selected)).padding(1, 0),
+        Widget::Space(1),
+    ]))
+}
+
+fn enter_arena(mut commands: Commands, mut stack: ResMut<PanelStack>, mut menu: ResMut<ArenaMenu>) {
+    *menu = ArenaMenu::default();
+    stack.open(&mut commands, ArenaWindow);
+}
+
+fn redraw_arena(
+    mut canvases: TileCanvases,
+    stack: Res<PanelStack>,
+    menu: Res<ArenaMenu>,
+    window: Single<(Entity, Ref<ArenaWindow>)>,
+) {
+    let (window, opened) = window.into_inner();
+    if !opened.is_added() && !menu.is_changed() {
+        return;
     }
+
+    canvases.draw(
+        window,
+        arena_panel(menu.selected).render(ARENA_WIDTH),
+        Vec3::Z * stack.z(window, 0.0),
+        TextAnchor::Center,
+    );
 }
 
-fn player_stage(
-    mut input: MessageReader<KeyboardInput>,
-    player: Single<Entity, With<Player>>,
-    targets: Query<(Entity, &BattleTarget)>,
+/// The fight menu only takes input on the turn of the player, while the
+/// arena has focus.
+fn activate_arena_input(
     mut commands: Commands,
+    stack: Res<PanelStack>,
+    battle: Option<Res<State<BattleState>>>,
+    windows: Query<(), With<ArenaWindow>>,
+    contexts: Query<Entity, With<ArenaInput>>,
 ) {
-    for input in input.read() {
-        if !input.state.is_pressed() {
-            continue;
+    let turn_changed = battle.as_ref().is_some_and(|battle| battle.is_changed());
+    if !stack.is_changed() && !turn_changed {
+        return;
+    }
+    let turn = battle.is_some_and(|battle| *battle.get() == BattleState::Player);
+    let active = turn && panel::focused(stack, windows);
+    input::set_active::<ArenaInput>(&mut commands, contexts, active);
+}
+
+fn navigate_arena<A: InputAction>(
+    step: isize,
+) -> impl Fn(On<Start<A>>, Query<(), With<ArenaInput>>, ResMut<ArenaMenu>) {
+    move |trigger, contexts, mut menu| {
+        if contexts.contains(trigger.context) {
+            menu.selected = menu
+                .selected
+                .saturating_add_signed(step)
+                .min(OPTIONS.len() - 1);
         }
-        match input.key_code {
-            KeyCode::Digit1 => {
-                commands.set_state(BattleState::PlayerResult);
+    }
+}
 
-                let (entity, target) = targets.iter().next().unwrap();
-                commands.trigger(Attack {
-                    entity,
-                    attacker: *player,
-                    damage: 1,
-                });
+fn confirm_option(
+    trigger: On<Start<Confirm>>,
+    contexts: Query<(), With<ArenaInput>>,
+    menu: Res<ArenaMenu>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context) {
+        commands.trigger(ChooseOption {
+            index: menu.selected,
+        });
+    }
+}
 
+fn select_option(
+    trigger: On<Start<SelectOption>>,
+    contexts: Query<(), With<ArenaInput>>,
+    mut menu: ResMut<ArenaMenu>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context)
+        && let Some(index) = SelectOption::index(trigger.value)
+        && index < OPTIONS.len()
+    {
+        menu.selected = index;
+        commands.trigger(ChooseOption { index });
+    }
+}
+
+/// Status effect that raises the defense of the player to (defense + 1) × 2
+/// until their next turn. The extra point makes blocking useful without armor.
+#[derive(Component)]
+#[require(Modifiers(vec![Modifier::mul(Stat::Defense, 2.0), Modifier::add(Stat::Defense, 1)]))]
+pub struct Blocking;
+
+impl Blocking {
+    fn expire(mut commands: Commands, effects: Query<Entity, With<Self>>) {
+        for effect in effects.iter() {
+            commands.entity(effect).despawn();
+        }
+    }
+}
+
+fn player_stage(
+    trigger: On<ChooseOption>,
+    stack: Res<PanelStack>,
+    windows: Query<(), With<ArenaWindow>>,
+    battle: Option<Res<State<BattleState>>>,
+    player: Single<(Entity, &Stats), With<Player>>,
+    targets: Query<(Entity, &BattleTarget, &Stats)>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut commands: Commands,
+) {
+    let turn = battle.is_some_and(|battle| *battle.get() == BattleState::Player);
+    if !turn || !panel::focused(stack, windows) {
+        return;
+    }
+
+    let (player, player_stats) = *player;
+    match trigger.index {
+        0 => {
+            // The arena can be entered with the toggle, without anyone to
+            // fight.
+            let Some((entity, _, stats)) = targets.iter().next() else {
+                info!("nothing to attack");
                 return;
-            }
-            KeyCode::Digit2 => {
-                commands.set_state(BattleState::PlayerResult);
-                return;
-            }
-            KeyCode::Digit3 => {
+            };
+            commands.set_state(BattleState::PlayerResult);
+            commands.trigger(Attack {
+                entity,
+                attacker: player,
+                damage: stats::damage(player_stats.attack, stats.defense),
+            });
+        }
+        1 => {
+            commands.set_state(BattleState::PlayerResult);
+            commands.spawn((Blocking, StatusEffectOf(player)));
+        }
+        2 => {
+            let fastest = targets
+                .iter()
+                .map(|(_, _, stats)| stats.speed)
+                .max()
+                .unwrap_or_default();
+            let chance = player_stats.speed.max(1) as f64
+                / (player_stats.speed.max(1) + fastest.max(0)) as f64;
+
+            if rng.random_bool(chance) {
+                info!("player escaped!");
+                commands.set_state(GameState::Overworld);
+            } else {
+                info!("player failed to escape");
                 commands.set_state(BattleState::PlayerResult);
-                return;
             }
-            _ => {}
         }
+        _ => {}
     }
 }
 
@@ -188,11 +324,12 @@ fn observe_death(trigger: On<Death>, target: Query<&BattleTarget>, mut commands:
 
 fn exit_arena(
     mut commands: Commands,
-    entities: Query<Entity, With<ArenaEntity>>,
+    mut stack: ResMut<PanelStack>,
+    windows: Query<Entity, With<ArenaWindow>>,
     targets: Query<Entity, With<BattleTarget>>,
 ) {
-    for entity in entities.iter() {
-        commands.entity(entity).despawn();
+    for window in windows.iter() {
+        stack.close(&mut commands, window);
     }
 
     for target in targets {
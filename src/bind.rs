@@ -0,0 +1,170 @@
+//! Tile widgets bound to components, redrawn when the components change.
+//!
+//! ```ignore
+//! app.add_plugins(bind::plugin::<&Health, Changed<Health>>);
+//!
+//! commands.spawn((
+//!     Binding::<&Health>::new(player, 12, |health| Widget::text(format!("HP {}", health.0))),
+//!     Transform::from_xyz(x, y, 20.0),
+//! ));
+//! ```
+//!
+//! Only the tiles whose glyph or colors differ from the last render are
+//! touched, so a readout that changes one digit updates one sprite. Panels
+//! that are not bound to a single entity draw the same way through a
+//! [`TileCanvas`] and [`TileCanvases`].
+
+use crate::{
+    layout::{Canvas, Widget},
+    tile::{TextAnchor, TileSprite},
+};
+use bevy::{
+    ecs::{
+        query::{QueryFilter, QueryItem, ReadOnlyQueryData},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+
+/// Redraws every [`Binding<D>`] whose source matches `F`.
+///
+/// `F` is usually `Changed` of the components that `D` reads, such as
+/// `Or<(Changed<Health>, Changed<Stats>)>` for `(&Health, &Stats)`.
+pub fn plugin<D, F>(app: &mut App)
+where
+    D: ReadOnlyQueryData + 'static,
+    F: QueryFilter + 'static,
+{
+    app.configure_sets(
+        PostUpdate,
+        BindingSystems.before(TransformSystems::Propagate),
+    )
+    .add_systems(PostUpdate, update_bindings::<D, F>.in_set(BindingSystems));
+}
+
+/// Where bindings are redrawn, so they can be ordered after the systems that
+/// compute their data.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BindingSystems;
+
+/// Draws the widget returned by `render` for the `D` of `source`.
+///
+/// The top left corner of the widget is placed at the [`Transform`] of this
+/// entity, in pixels, and the tiles are drawn at its z.
+#[derive(Component)]
+#[require(Transform, TileCanvas)]
+pub struct Binding<D: ReadOnlyQueryData + 'static> {
+    pub source: Entity,
+    pub width: u32,
+    render: fn(QueryItem<'_, '_, D>) -> Widget<'static>,
+}
+
+impl<D: ReadOnlyQueryData + 'static> Binding<D> {
+    pub fn new(
+        source: Entity,
+        width: u32,
+        render: fn(QueryItem<'_, '_, D>) -> Widget<'static>,
+    ) -> Self {
+        Self {
+            source,
+            width,
+            render,
+        }
+    }
+}
+
+/// Tiles drawn with [`TileCanvases::draw`], which remembers the last canvas
+/// so the next draw only touches the cells that differ.
+///
+/// Panels insert it next to their marker and draw into it whenever their
+/// state changes, instead of respawning every tile.
+#[derive(Component, Default)]
+pub struct TileCanvas {
+    last: Option<(Canvas, Vec3)>,
+}
+
+/// The tiles drawn by a [`TileCanvas`], in the order of [`Canvas::cells`].
+#[derive(Component, Default)]
+#[relationship_target(relationship = BoundTileOf, linked_spawn)]
+pub struct BoundTiles(Vec<Entity>);
+
+#[derive(Component)]
+#[relationship(relationship_target = BoundTiles)]
+pub struct BoundTileOf(pub Entity);
+
+/// Draws canvases onto the tiles of [`TileCanvas`] entities.
+#[derive(SystemParam)]
+pub struct TileCanvases<'w, 's> {
+    commands: Commands<'w, 's>,
+    canvases: Query<'w, 's, (&'static mut TileCanvas, Option<&'static BoundTiles>)>,
+    tiles: Query<'w, 's, (&'static mut TileSprite, &'static mut Transform), With<BoundTileOf>>,
+}
+
+impl TileCanvases<'_, '_> {
+    /// Draws `canvas` on the tiles of `entity`, laid out around `origin` in
+    /// pixels like [`Canvas::tiles`].
+    ///
+    /// A canvas of the same size as the last one updates the changed cells
+    /// and moves the tiles if `origin` moved. Otherwise the tiles are
+    /// respawned.
+    pub fn draw(&mut self, entity: Entity, canvas: Canvas, origin: Vec3, anchor: TextAnchor) {
+        let Ok((mut drawn, bound)) = self.canvases.get_mut(entity) else {
+            return;
+        };
+        let bound = bound.map_or(&[][..], |bound| &bound.0[..]);
+
+        match &drawn.last {
+            Some((last, from)) if last.size == canvas.size && bound.len() == canvas.cells.len() => {
+                let offset = origin - *from;
+                for (index, cell) in canvas.cells.iter().enumerate() {
+                    let Ok((mut tile, mut transform)) = self.tiles.get_mut(bound[index]) else {
+                        continue;
+                    };
+                    if last.cells[index] != *cell {
+                        *tile = *cell;
+                    }
+                    if offset != Vec3::ZERO {
+                        transform.translation += offset;
+                    }
+                }
+            }
+            _ => {
+                for tile in bound {
+                    self.commands.entity(*tile).despawn();
+                }
+                for (tile, position) in canvas.tiles(0, 0, anchor) {
+                    self.commands.spawn((
+                        tile,
+                        Transform::from_translation(origin + position.extend(0.0)),
+                        BoundTileOf(entity),
+                    ));
+                }
+            }
+        }
+        drawn.last = Some((canvas, origin));
+    }
+}
+
+fn update_bindings<D, F>(
+    mut canvases: TileCanvases,
+    bindings: Query<(Entity, Ref<Binding<D>>, &Transform), Without<BoundTileOf>>,
+    sources: Query<D>,
+    changed: Query<(), F>,
+) where
+    D: ReadOnlyQueryData + 'static,
+    F: QueryFilter + 'static,
+{
+    for (entity, binding, transform) in bindings.iter() {
+        if !binding.is_added() && !changed.contains(binding.source) {
+            continue;
+        }
+        let Ok(data) = sources.get(binding.source) else {
+            // The source is gone, so the readout would be stale.
+            canvases.commands.entity(entity).despawn();
+            continue;
+        };
+
+        let canvas = (binding.render)(data).render(binding.width);
+        canvases.draw(entity, canvas, transform.translation, TextAnchor::TopLeft);
+    }
+}
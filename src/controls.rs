@@ -0,0 +1,744 @@
+//! Rebindable controls, stored in [`CONTROLS_PATH`] of the [`DataDir`].
+//!
+//! The input contexts in [`input`](crate::input) are built from the
+//! [`Controls`] resource and rebuilt whenever it changes. `F1` opens a
+//! screen to rebind every [`Control`], which writes the result back to disk.
+
+use crate::{
+    DataDir,
+    bind::{TileCanvas, TileCanvases},
+    input::{
+        self, Cancel, Confirm, ControlsInput, NavigateDown, NavigateLeft, NavigateRight, NavigateUp,
+    },
+    layout::Widget,
+    panel::{self, Modal, PanelStack},
+    text::StyledText,
+    tile::TextAnchor,
+};
+use bevy::{
+    color::palettes::tailwind::{GRAY_400, RED_400, YELLOW_300},
+    prelude::*,
+};
+use bevy_enhanced_input::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+pub fn plugin(app: &mut App) {
+    let path = app
+        .world()
+        .get_resource::<DataDir>()
+        .cloned()
+        .unwrap_or_default()
+        .0
+        .join(CONTROLS_PATH);
+    app.insert_resource(Controls::load_or_default(path))
+        .init_resource::<ControlsMenu>()
+        .add_observer(navigate_controls::<NavigateUp>(-1))
+        .add_observer(navigate_controls::<NavigateDown>(1))
+        .add_observer(navigate_slots::<NavigateLeft>(-1))
+        .add_observer(navigate_slots::<NavigateRight>(1))
+        .add_observer(start_listening)
+        .add_observer(close_controls)
+        .add_systems(
+            Update,
+            (
+                open_controls.run_if(panel::no_modal),
+                (listen_for_input, redraw_controls)
+                    .chain()
+                    .run_if(any_with_component::<ControlsWindow>),
+                activate_controls_input,
+            )
+                .chain(),
+        );
+}
+
+/// Bumped whenever [`Controls`] changes in a way that breaks older files.
+pub const CONTROLS_VERSION: u32 = 1;
+pub const CONTROLS_PATH: &str = "controls.ron";
+
+/// Something the player can bind inputs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Control {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    OpenInventory,
+    Confirm,
+    Cancel,
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    /// Picks the numbered option, starting from 1.
+    SelectOption(u8),
+    EquipItem,
+    DropItem,
+    SortItems,
+    Look,
+    /// Moves to the next entity on the tile under the look cursor.
+    CycleLook,
+    OpenControls,
+    Save,
+    Load,
+    Screenshot,
+    ToggleArena,
+    Quit,
+}
+
+/// Where a [`Control`] is read. Two controls may only share an input if they
+/// are never read at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlContext {
+    Overworld,
+    Arena,
+    Inventory,
+    Menu,
+    Look,
+    /// Read outside of the input contexts, at any time.
+    Global,
+}
+
+impl Control {
+    pub const ALL: [Self; 31] = [
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::OpenInventory,
+        Self::Confirm,
+        Self::Cancel,
+        Self::NavigateUp,
+        Self::NavigateDown,
+        Self::NavigateLeft,
+        Self::NavigateRight,
+        Self::SelectOption(1),
+        Self::SelectOption(2),
+        Self::SelectOption(3),
+        Self::SelectOption(4),
+        Self::SelectOption(5),
+        Self::SelectOption(6),
+        Self::SelectOption(7),
+        Self::SelectOption(8),
+        Self::SelectOption(9),
+        Self::EquipItem,
+        Self::DropItem,
+        Self::SortItems,
+        Self::Look,
+        Self::CycleLook,
+        Self::OpenControls,
+        Self::Save,
+        Self::Load,
+        Self::Screenshot,
+        Self::ToggleArena,
+        Self::Quit,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Self::MoveUp => "Move up".into(),
+            Self::MoveDown => "Move down".into(),
+            Self::MoveLeft => "Move left".into(),
+            Self::MoveRight => "Move right".into(),
+            Self::OpenInventory => "Inventory".into(),
+            Self::Confirm => "Confirm".into(),
+            Self::Cancel => "Cancel".into(),
+            Self::NavigateUp => "Menu up".into(),
+            Self::NavigateDown => "Menu down".into(),
+            Self::NavigateLeft => "Menu left".into(),
+            Self::NavigateRight => "Menu right".into(),
+            Self::SelectOption(n) => format!("Option {n}"),
+            Self::EquipItem => "Equip".into(),
+            Self::DropItem => "Drop".into(),
+            Self::SortItems => "Sort".into(),
+            Self::Look => "Look".into(),
+            Self::CycleLook => "Look next".into(),
+            Self::OpenControls => "Controls".into(),
+            Self::Save => "Save".into(),
+            Self::Load => "Load".into(),
+            Self::Screenshot => "Screenshot".into(),
+            Self::ToggleArena => "Arena".into(),
+            Self::Quit => "Quit".into(),
+        }
+    }
+
+    pub fn contexts(self) -> &'static [ControlContext] {
+        use ControlContext::*;
+        match self {
+            // Also move the look cursor.
+            Self::MoveUp | Self::MoveDown | Self::MoveLeft | Self::MoveRight => &[Overworld, Look],
+            // Also closes the inventory.
+            Self::OpenInventory => &[Overworld, Inventory],
+            Self::Confirm => &[Arena, Menu],
+            Self::Cancel => &[Inventory, Menu, Look],
+            Self::NavigateUp | Self::NavigateDown => &[Inventory, Arena, Menu],
+            Self::NavigateLeft | Self::NavigateRight => &[Menu],
+            Self::SelectOption(_) => &[Arena, Menu],
+            Self::EquipItem | Self::DropItem | Self::SortItems => &[Inventory],
+            // Also leaves look mode.
+            Self::Look => &[Overworld, Look],
+            Self::CycleLook => &[Look],
+            Self::OpenControls
+            | Self::Save
+            | Self::Load
+            | Self::Screenshot
+            | Self::ToggleArena
+            | Self::Quit => &[Global],
+        }
+    }
+
+    /// Whether the two controls can be read at the same time.
+    pub fn overlaps(self, other: Self) -> bool {
+        let (a, b) = (self.contexts(), other.contexts());
+        a.contains(&ControlContext::Global)
+            || b.contains(&ControlContext::Global)
+            || a.iter().any(|context| b.contains(context))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ControlInput {
+    Key(KeyCode),
+    Gamepad(GamepadButton),
+}
+
+impl ControlInput {
+    pub fn label(self) -> String {
+        let name = match self {
+            Self::Key(key) => format!("{key:?}"),
+            Self::Gamepad(button) => format!("Pad {button:?}"),
+        };
+        ["Key", "Digit", "Arrow"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix).filter(|s| !s.is_empty()))
+            .map_or(name.clone(), str::to_owned)
+    }
+}
+
+impl From<ControlInput> for Binding {
+    fn from(input: ControlInput) -> Self {
+        match input {
+            ControlInput::Key(key) => key.into(),
+            ControlInput::Gamepad(button) => button.into(),
+        }
+    }
+}
+
+/// The inputs bound to every [`Control`].
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Controls {
+    pub version: u32,
+    pub bindings: BTreeMap<Control, Vec<ControlInput>>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        use ControlInput::{Gamepad, Key};
+        use GamepadButton as Pad;
+
+        let mut bindings = BTreeMap::from([
+            (
+                Control::MoveUp,
+                vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            ),
+            (
+                Control::MoveDown,
+                vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            ),
+            (
+                Control::MoveLeft,
+                vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            ),
+            (
+                Control::MoveRight,
+                vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            ),
+            (
+                Control::OpenInventory,
+                vec![Key(KeyCode::KeyI), Gamepad(Pad::North)],
+            ),
+            (
+                Control::Confirm,
+                vec![
+                    Key(KeyCode::Enter),
+                    Key(KeyCode::Space),
+                    Gamepad(Pad::South),
+                ],
+            ),
+            (
+                Control::Cancel,
+                vec![Key(KeyCode::Backspace), Gamepad(Pad::East)],
+            ),
+            (
+                Control::NavigateUp,
+                vec![Key(KeyCode::ArrowUp), Gamepad(Pad::DPadUp)],
+            ),
+            (
+                Control::NavigateDown,
+                vec![Key(KeyCode::ArrowDown), Gamepad(Pad::DPadDown)],
+            ),
+            (
+                Control::NavigateLeft,
+                vec![Key(KeyCode::ArrowLeft), Gamepad(Pad::DPadLeft)],
+            ),
+            (
+                Control::NavigateRight,
+                vec![Key(KeyCode::ArrowRight), Gamepad(Pad::DPadRight)],
+            ),
+            (
+                Control::EquipItem,
+                vec![Key(KeyCode::Enter), Gamepad(Pad::South)],
+            ),
+            (
+                Control::DropItem,
+                vec![Key(KeyCode::KeyX), Gamepad(Pad::West)],
+            ),
+            (
+                Control::SortItems,
+                vec![Key(KeyCode::KeyS), Gamepad(Pad::Select)],
+            ),
+            (
+                Control::Look,
+                vec![Key(KeyCode::KeyL), Gamepad(Pad::LeftTrigger)],
+            ),
+            (
+                Control::CycleLook,
+                vec![Key(KeyCode::Tab), Gamepad(Pad::RightTrigger)],
+            ),
+            (Control::OpenControls, vec![Key(KeyCode::F1)]),
+            (Control::Save, vec![Key(KeyCode::F5)]),
+            (Control::Load, vec![Key(KeyCode::F9)]),
+            (Control::Screenshot, vec![Key(KeyCode::F12)]),
+            (Control::ToggleArena, vec![Key(KeyCode::KeyP)]),
+            (Control::Quit, vec![Key(KeyCode::Escape)]),
+        ]);
+        const DIGITS: [KeyCode; 9] = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (n, key) in (1..).zip(DIGITS) {
+            bindings.insert(Control::SelectOption(n), vec![Key(key)]);
+        }
+
+        Self {
+            version: CONTROLS_VERSION,
+            bindings,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ControlsError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    Version(u32),
+}
+
+impl core::fmt::Display for ControlsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Serialize(e) => e.fmt(f),
+            Self::Deserialize(e) => e.fmt(f),
+            Self::Version(version) => {
+                write!(
+                    f,
+                    "controls version {version} is not supported, expected {CONTROLS_VERSION}"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ControlsError {}
+
+impl Controls {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ControlsError> {
+        let ron = std::fs::read_to_string(path).map_err(ControlsError::Io)?;
+        let mut controls = ron::from_str::<Self>(&ron).map_err(ControlsError::Deserialize)?;
+        if controls.version != CONTROLS_VERSION {
+            return Err(ControlsError::Version(controls.version));
+        }
+        // Controls added since the file was written keep their defaults.
+        for (control, inputs) in Self::default().bindings {
+            controls.bindings.entry(control).or_insert(inputs);
+        }
+        Ok(controls)
+    }
+
+    /// Loads the controls, falling back to the defaults if the file is
+    /// missing or broken.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match Self::load(path) {
+            Ok(controls) => controls,
+            Err(ControlsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(e) => {
+                warn!(
+                    "failed to load controls from {}, using defaults: {e}",
+                    path.display()
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ControlsError> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(ControlsError::Serialize)?;
+        std::fs::write(path, ron).map_err(ControlsError::Io)
+    }
+
+    pub fn inputs(&self, control: Control) -> &[ControlInput] {
+        self.bindings.get(&control).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bindings for `controls`, for the `Bindings` of an input action.
+    pub fn bindings(&self, controls: &[Control]) -> Vec<Binding> {
+        controls
+            .iter()
+            .flat_map(|control| self.inputs(*control))
+            .map(|input| Binding::from(*input))
+            .collect()
+    }
+
+    /// Whether a key or button bound to `control` was pressed this frame, on
+    /// any gamepad. For controls read outside of the input contexts.
+    pub fn just_pressed(
+        &self,
+        control: Control,
+        keys: &ButtonInput<KeyCode>,
+        gamepads: &Query<&Gamepad>,
+    ) -> bool {
+        self.inputs(control).iter().any(|input| match input {
+            ControlInput::Key(key) => keys.just_pressed(*key),
+            ControlInput::Gamepad(button) => {
+                gamepads.iter().any(|gamepad| gamepad.just_pressed(*button))
+            }
+        })
+    }
+
+    /// Other controls that `input` is bound to and that are read at the same
+    /// time as `control`.
+    pub fn conflicts(&self, control: Control, input: ControlInput) -> Vec<Control> {
+        self.bindings
+            .iter()
+            .filter(|(other, inputs)| {
+                **other != control && control.overlaps(**other) && inputs.contains(&input)
+            })
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// Binds `input` to `control` in place of its input at `slot`, or next to
+    /// its other inputs if `slot` is past the last one. Returns the
+    /// conflicting controls instead if there are any.
+    pub fn rebind(
+        &mut self,
+        control: Control,
+        slot: usize,
+        input: ControlInput,
+    ) -> Result<(), Vec<Control>> {
+        let conflicts = self.conflicts(control, input);
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+
+        let inputs = self.bindings.entry(control).or_default();
+        if inputs.contains(&input) {
+            return Ok(());
+        }
+        match inputs.get_mut(slot) {
+            Some(old) => *old = input,
+            None => inputs.push(input),
+        }
+        Ok(())
+    }
+}
+
+/// The [`Panel`](crate::panel::Panel) that lists the controls.
+#[derive(Component)]
+#[require(Modal, TileCanvas)]
+struct ControlsWindow;
+
+/// The state of the controls screen.
+#[derive(Resource, Default)]
+pub struct ControlsMenu {
+    selected: usize,
+    /// The first control shown, so the selected one stays in view.
+    scroll: usize,
+    /// The input of the selected control that is rebound. One past its last
+    /// input adds a new one.
+    slot: usize,
+    /// Whether the next pressed input is bound to the selected control.
+    listening: bool,
+    /// Set once every input is released after listening starts, so the
+    /// press that started listening is not bound.
+    armed: bool,
+    /// The result of the last rebind, shown at the bottom of the screen.
+    status: Option<StyledText<'static>>,
+}
+
+impl ControlsMenu {
+    fn select(&mut self, selected: usize) {
+        let len = Control::ALL.len();
+        self.selected = selected.min(len - 1);
+        self.slot = 0;
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+        self.scroll = self.scroll.min(len.saturating_sub(VISIBLE_ROWS));
+    }
+}
+
+/// Width of the controls panel in tiles.
+const CONTROLS_WIDTH: u32 = 44;
+/// Number of controls shown at once.
+const VISIBLE_ROWS: usize = 16;
+
+fn open_controls(
+    mut commands: Commands,
+    mut stack: ResMut<PanelStack>,
+    mut menu: ResMut<ControlsMenu>,
+    controls: Res<Controls>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    window: Option<Single<(), With<ControlsWindow>>>,
+) {
+    if window.is_none() && controls.just_pressed(Control::OpenControls, &keys, &gamepads) {
+        stack.open(&mut commands, ControlsWindow);
+        *menu = ControlsMenu::default();
+    }
+}
+
+/// Run condition for global controls, such as quitting, that must not fire
+/// while the pressed input is being bound.
+pub fn not_listening(menu: Res<ControlsMenu>) -> bool {
+    !menu.listening
+}
+
+/// Cancel stops listening, or closes the screen when not listening.
+fn close_controls(
+    trigger: On<Start<Cancel>>,
+    contexts: Query<(), With<ControlsInput>>,
+    mut stack: ResMut<PanelStack>,
+    mut menu: ResMut<ControlsMenu>,
+    window: Single<Entity, With<ControlsWindow>>,
+    mut commands: Commands,
+) {
+    if !contexts.contains(trigger.context) {
+        return;
+    }
+    if menu.listening {
+        menu.listening = false;
+        menu.status = None;
+    } else {
+        stack.close(&mut commands, *window);
+    }
+}
+
+fn navigate_controls<A: InputAction>(
+    step: isize,
+) -> impl Fn(On<Start<A>>, Query<(), With<ControlsInput>>, ResMut<ControlsMenu>) {
+    move |trigger, contexts, mut menu| {
+        if contexts.contains(trigger.context) && !menu.listening {
+            let selected = menu.selected.saturating_add_signed(step);
+            menu.select(selected);
+        }
+    }
+}
+
+fn navigate_slots<A: InputAction>(
+    step: isize,
+) -> impl Fn(On<Start<A>>, Query<(), With<ControlsInput>>, ResMut<ControlsMenu>, Res<Controls>) {
+    move |trigger, contexts, mut menu, controls| {
+        if contexts.contains(trigger.context) && !menu.listening {
+            let len = controls.inputs(Control::ALL[menu.selected]).len();
+            menu.slot = menu.slot.saturating_add_signed(step).min(len);
+        }
+    }
+}
+
+fn start_listening(
+    trigger: On<Start<Confirm>>,
+    contexts: Query<(), With<ControlsInput>>,
+    mut menu: ResMut<ControlsMenu>,
+    controls: Res<Controls>,
+) {
+    if !contexts.contains(trigger.context) || menu.listening {
+        return;
+    }
+    menu.listening = true;
+    menu.armed = false;
+    menu.status = Some(StyledText::from(
+        match controls.inputs(Control::Cancel).first() {
+            Some(cancel) => format!("Press a key or button, {} to cancel", cancel.label()),
+            None => "Press a key or button".to_string(),
+        },
+    ));
+}
+
+/// Binds the next pressed key or gamepad button while listening.
+fn listen_for_input(
+    mut menu: ResMut<ControlsMenu>,
+    mut controls: ResMut<Controls>,
+    data_dir: Res<DataDir>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    if !menu.listening {
+        return;
+    }
+    if !menu.armed {
+        let held = keys.get_pressed().next().is_some()
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad.get_pressed().next().is_some());
+        if !held {
+            menu.armed = true;
+        }
+        return;
+    }
+
+    let Some(input) = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| ControlInput::Key(*key))
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+                .map(|button| ControlInput::Gamepad(*button))
+        })
+    else {
+        return;
+    };
+
+    let control = Control::ALL[menu.selected];
+    menu.listening = false;
+    menu.status = Some(match controls.rebind(control, menu.slot, input) {
+        Ok(()) => {
+            if let Err(e) = controls.save(data_dir.0.join(CONTROLS_PATH)) {
+                error!("failed to save controls: {e}");
+            }
+            StyledText::from(format!("{} bound to {}", control.label(), input.label()))
+        }
+        Err(conflicts) => StyledText::new().fg(
+            format!(
+                "{} is already used by {}",
+                input.label(),
+                conflicts
+                    .iter()
+                    .map(|c| c.label())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            RED_400.into(),
+        ),
+    });
+}
+
+fn redraw_controls(
+    mut canvases: TileCanvases,
+    stack: Res<PanelStack>,
+    menu: Res<ControlsMenu>,
+    controls: Res<Controls>,
+    window: Single<(Entity, Ref<ControlsWindow>)>,
+) {
+    let (window, opened) = window.into_inner();
+    if !menu.is_changed() && !controls.is_changed() && !opened.is_added() {
+        return;
+    }
+
+    // The selected control shows which of its inputs is rebound, with a
+    // trailing `+` for adding one.
+    let rows = Control::ALL
+        .iter()
+        .enumerate()
+        .skip(menu.scroll)
+        .take(VISIBLE_ROWS)
+        .map(|(row, control)| {
+            let selected = row == menu.selected;
+            let inputs = controls.inputs(*control);
+            let mut text = StyledText::new().push(format!("{:<12} ", control.label()));
+            for (slot, input) in inputs.iter().enumerate() {
+                if slot > 0 {
+                    text = text.push(", ");
+                }
+                text = if selected && slot == menu.slot {
+                    text.fg(input.label(), YELLOW_300.into())
+                } else {
+                    text.push(input.label())
+                };
+            }
+            if selected {
+                let color = if menu.slot >= inputs.len() {
+                    YELLOW_300
+                } else {
+                    GRAY_400
+                };
+                text = text.fg(" +", color.into());
+            }
+            text
+        });
+    let arrows = format!(
+        "{}{}",
+        if menu.scroll > 0 { '^' } else { ' ' },
+        if menu.scroll + VISIBLE_ROWS < Control::ALL.len() {
+            'v'
+        } else {
+            ' '
+        },
+    );
+    let status = menu.status.clone().unwrap_or_else(|| {
+        StyledText::from("ENTER rebind  LEFT/RIGHT pick input  BACKSPACE close")
+    });
+    let panel = Widget::border(
+        Widget::column([
+            Widget::Space(1),
+            Widget::centered("C O N T R O L S"),
+            Widget::Space(1),
+            Widget::Separator,
+            Widget::list(rows, Some(menu.selected - menu.scroll)),
+            Widget::centered(arrows),
+            Widget::Separator,
+            Widget::paragraph(status),
+        ])
+        .padding(1, 0),
+    );
+
+    canvases.draw(
+        window,
+        panel.render(CONTROLS_WIDTH),
+        Vec3::Z * stack.z(window, 0.0),
+        TextAnchor::Center,
+    );
+}
+
+/// The actions of the controls screen stay active while listening, so
+/// [`Cancel`] can stop it. The other actions ignore input then.
+fn activate_controls_input(
+    mut commands: Commands,
+    stack: Res<PanelStack>,
+    windows: Query<(), With<ControlsWindow>>,
+    contexts: Query<Entity, With<ControlsInput>>,
+    mut active: Local<bool>,
+) {
+    let focused = panel::focused(stack, windows);
+    if focused != *active {
+        *active = focused;
+        input::set_active::<ControlsInput>(&mut commands, contexts, focused);
+    }
+}
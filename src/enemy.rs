@@ -1,31 +1,86 @@
 use crate::{
     GameState,
     arena::{Attack, BattleState, Death},
-    equipment::{Equipment, Health, HealthUnit},
+    equipment::{Equipment, Equipped},
+    item::Item,
+    level::XpReward,
+    loot::{LootRoll, LootTable},
+    mapgen::{self, Descended},
     observer::ObserverSystem,
     player::Player,
-    tile::{MoveIntent, Solid, TilePosition, TileSprite, TileZ},
+    stats::{self, BaseStats, Health, Stats},
+    tile::{MoveIntent, PositionQuery, Solid, TilePosition, TileSprite, TileZ, Wall},
+    view::MapBounds,
 };
 use bevy::{color::palettes::tailwind::BLUE_300, prelude::*};
 use bevy_rand::{global::GlobalRng, prelude::WyRand};
 use rand::{Rng, seq::IteratorRandom};
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, spawn_enemy)
+    app.add_systems(PostStartup, spawn_enemy)
+        .add_observer(respawn_enemies)
         .add_systems(Update, walk.run_if(in_state(GameState::Overworld)))
         .add_systems(OnEnter(BattleState::EnemyResult), Droid::trigger_attack);
 }
 
-fn spawn_enemy(mut commands: Commands) {
+/// How many steps away from the player a new droid is placed, at most.
+const SPAWN_DISTANCE: u32 = 20;
+
+/// Runs after the first level was spawned in [`Startup`].
+fn spawn_enemy(
+    mut commands: Commands,
+    player: Single<&TilePosition, With<Player>>,
+    bounds: Res<MapBounds>,
+    walls: PositionQuery<(), With<Wall>>,
+) {
+    spawn_droid_near(&mut commands, player.0, bounds.0, &walls);
+}
+
+/// Spawns a droid on the floor tile furthest from `player` within
+/// [`SPAWN_DISTANCE`] steps, so it can always walk up to them.
+fn spawn_droid_near(
+    commands: &mut Commands,
+    player: IVec2,
+    bounds: IRect,
+    walls: &PositionQuery<(), With<Wall>>,
+) {
+    let is_wall = |cell| walls.iter(&TilePosition(cell)).next().is_some();
+    match mapgen::furthest_floor(player, bounds, SPAWN_DISTANCE, is_wall) {
+        Some(position) => spawn_droid(commands, position),
+        None => warn!("no floor around the player to spawn a droid on"),
+    }
+}
+
+fn spawn_droid(commands: &mut Commands, position: IVec2) {
     commands.spawn((
         Droid,
-        TilePosition(IVec2::new(10, 10)),
+        TilePosition(position),
         related!(Equipment[
-            HealthUnit(5),
+            (Item::new("cracked_health_unit"), Equipped),
+            LootRoll::any(),
         ]),
     ));
 }
 
+/// Replaces the enemies of the previous level, so new ones roll their loot at
+/// the new [`Depth`](crate::mapgen::Depth).
+fn respawn_enemies(
+    _: On<Descended>,
+    mut commands: Commands,
+    player: Single<&TilePosition, With<Player>>,
+    bounds: Res<MapBounds>,
+    walls: PositionQuery<(), With<Wall>>,
+    enemies: Query<(Entity, Option<&Equipment>), With<Enemy>>,
+) {
+    for (enemy, equipment) in enemies.iter() {
+        for item in equipment.into_iter().flat_map(|e| e.iter()) {
+            commands.entity(item).despawn();
+        }
+        commands.entity(enemy).despawn();
+    }
+    spawn_droid_near(&mut commands, player.0, bounds.0, &walls);
+}
+
 #[derive(Default, Component)]
 pub struct Enemy;
 
@@ -33,9 +88,12 @@ pub struct Enemy;
 #[require(
     TileSprite::DROID,
     WalkTimer::from_secs_prob(0.2, 0.2),
-    TileZ(1),
+    TileZ(2),
     Solid,
     Enemy,
+    BaseStats::DROID,
+    LootTable::DROID,
+    XpReward(5),
     ObserverSystem::<Attack>::on(Self::observe_hit),
 )]
 pub struct Droid;
@@ -60,23 +118,30 @@ impl Droid {
     }
 
     fn trigger_attack(
-        player: Single<Entity, With<Player>>,
-        droids: Query<Entity, With<Self>>,
+        player: Single<(Entity, &Stats), With<Player>>,
+        droids: Query<(Entity, &Stats), With<Self>>,
         mut rng: Single<&mut WyRand, With<GlobalRng>>,
         mut commands: Commands,
     ) {
-        for droid in droids {
+        let (player, player_stats) = *player;
+        for (droid, stats) in droids {
             if rng.random_bool(0.5) {
                 commands.trigger(Attack {
-                    entity: *player,
+                    entity: player,
                     attacker: droid,
-                    damage: 1,
+                    damage: stats::damage(stats.attack, player_stats.defense),
                 });
             }
         }
     }
 }
 
+impl BaseStats {
+    /// Hits the player for 2 through the starting shield, or 1 while they
+    /// block.
+    pub const DROID: Self = Self::new(6, 0, 4, 0);
+}
+
 impl TileSprite {
     pub const DROID: Self = Self {
         ascii: b'd',
@@ -87,8 +152,8 @@ impl TileSprite {
 
 #[derive(Component)]
 #[require(LastWalk)]
-struct WalkTimer {
-    timer: Timer,
+pub(crate) struct WalkTimer {
+    pub(crate) timer: Timer,
     prob: f32,
 }
 
@@ -104,7 +169,7 @@ impl WalkTimer {
 }
 
 #[derive(Default, Component)]
-struct LastWalk(IVec2);
+pub(crate) struct LastWalk(pub(crate) IVec2);
 
 fn walk(
     time: Res<Time>,
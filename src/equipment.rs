@@ -1,136 +1,480 @@
-use crate::tile::{TextAnchor, text_tiles};
-use bevy::prelude::*;
-use bevy_query_observer::{AddStartObserver, Start};
+use crate::{
+    TILE_SIZE,
+    bind::{TileCanvas, TileCanvases},
+    input,
+    input::{
+        Cancel, DropItem, EquipItem, InventoryInput, NavigateDown, NavigateUp, OpenInventory,
+        SortItems,
+    },
+    item::{Rarity, Slot},
+    layout::{Align, Canvas, Widget},
+    panel::{self, Modal, PanelStack, PanelTileOf},
+    player::Player,
+    stats::{Modifier, Modifiers},
+    text::StyledText,
+    tile::{TextAnchor, TilePosition, TileZ},
+};
+use bevy::{input::mouse::MouseWheel, prelude::*, window::PrimaryWindow};
+use bevy_enhanced_input::prelude::*;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(
-        Update,
-        (
-            (health_unit_display, shield_unit_display),
-            display_equipment,
+    app.init_resource::<EquipmentPanel>()
+        .add_message::<InventoryCommand>()
+        .add_observer(open_equipment_panel)
+        .add_observer(close_equipment_panel)
+        .add_observer(inventory_command::<NavigateUp>(InventoryCommand::Up))
+        .add_observer(inventory_command::<NavigateDown>(InventoryCommand::Down))
+        .add_observer(inventory_command::<EquipItem>(InventoryCommand::Equip))
+        .add_observer(inventory_command::<DropItem>(InventoryCommand::Drop))
+        .add_observer(inventory_command::<SortItems>(InventoryCommand::Sort))
+        .add_systems(
+            Update,
+            (
+                navigate_equipment_panel.run_if(panel::focused::<EquipmentWindow>),
+                display_equipment.run_if(any_with_component::<EquipmentWindow>),
+            )
+                .chain(),
         )
-            .chain(),
-    )
-    .add_start_observer(HealthUnit::observe_insertion);
+        .add_systems(PostUpdate, activate_inventory_input);
 }
 
-#[derive(Component, Clone, Deref, DerefMut, Default)]
-pub struct Health(pub i32);
+#[derive(Component)]
+#[relationship_target(relationship = EquipmentOf)]
+pub struct Equipment(Vec<Entity>);
 
-#[derive(Component, Clone, Copy)]
-#[require(Name::new("Health Unit"), EquipmentDisplay, Tooltips::HEALTH_UNIT)]
-#[component(immutable)]
-pub struct HealthUnit(pub i32);
+#[derive(Component)]
+#[relationship(relationship_target = Equipment)]
+pub struct EquipmentOf(pub Entity);
 
-impl HealthUnit {
-    fn observe_insertion(data: Start<(&Self, &EquipmentOf)>, mut commands: Commands) {
-        let (&health, target) = data.into_inner();
+#[derive(Default, Component, PartialEq, Eq)]
+pub struct EquipmentDisplay(pub String);
 
-        commands
-            .entity(target.0)
-            .entry::<Health>()
-            .or_default()
-            .and_modify(move |mut h| h.0 += health.0);
+impl EquipmentDisplay {
+    pub fn from_modifiers(modifiers: &[Modifier]) -> Self {
+        Self(
+            modifiers
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
     }
 }
 
-fn health_unit_display(mut units: Query<(&HealthUnit, &mut EquipmentDisplay)>) {
-    for (unit, mut display) in units.iter_mut() {
-        display.0 = format!("x{}", unit.0);
+/// Marks an item whose [`Modifiers`] apply to its owner.
+#[derive(Default, Component)]
+pub struct Equipped;
+
+/// An item lying on the ground.
+#[derive(Default, Component)]
+#[require(TileZ(1))]
+pub struct Dropped;
+
+#[derive(Default, Component)]
+pub struct Tooltips(pub String);
+
+/// The [`Panel`] that lists the equipment of the player.
+#[derive(Component)]
+#[require(Modal, TileCanvas)]
+struct EquipmentWindow;
+
+/// The details of the selected item, right of the [`EquipmentWindow`].
+#[derive(Component)]
+#[require(TileCanvas)]
+struct EquipmentTooltip;
+
+/// Width of the equipment panel in tiles.
+const EQUIPMENT_WIDTH: u32 = 33;
+/// Number of item rows visible in the equipment panel.
+const VISIBLE_ROWS: usize = 13;
+/// Width of the text area of the tooltip panel.
+const TOOLTIP_WIDTH: usize = 22;
+
+/// The rows above the item list.
+fn equipment_header(sort: EquipmentSort, arrows: String) -> Widget<'static> {
+    Widget::column([
+        Widget::Space(1),
+        Widget::centered("E Q U I P M E N T"),
+        Widget::Space(1),
+        Widget::Separator,
+        Widget::row([
+            Widget::text(format!("sort: {}", sort.label())),
+            Widget::Text(arrows.into(), Align::Right),
+        ]),
+    ])
+}
+
+/// The equipment panel with `list` drawn between the header and the hints.
+fn equipment_panel<'a>(header: Widget<'a>, list: Widget<'a>) -> Widget<'a> {
+    Widget::border(Widget::column([
+        header,
+        list,
+        Widget::Separator,
+        Widget::centered("ENTER equip  X drop  S sort"),
+    ]))
+}
+
+/// The tiles the item rows are drawn on, with the panel centered on the
+/// screen like [`TextAnchor::Center`] draws it.
+fn equipment_rows() -> IRect {
+    let inner = EQUIPMENT_WIDTH - 2;
+    let header = equipment_header(EquipmentSort::default(), String::new());
+    let header_height = header.height(inner) as i32;
+    let height =
+        equipment_panel(header, Widget::Space(VISIBLE_ROWS as u32)).height(EQUIPMENT_WIDTH);
+
+    // Below the top border and the header, right of the left border.
+    let top = (height / 2) as i32 - 1 - header_height;
+    let left = -((EQUIPMENT_WIDTH / 2) as i32) + 1;
+    IRect::new(
+        left,
+        top - VISIBLE_ROWS as i32 + 1,
+        left + inner as i32 - 1,
+        top,
+    )
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EquipmentSort {
+    #[default]
+    Acquired,
+    Name,
+    Equipped,
+}
+
+impl EquipmentSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Acquired => Self::Name,
+            Self::Name => Self::Equipped,
+            Self::Equipped => Self::Acquired,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Acquired => "newest",
+            Self::Name => "name",
+            Self::Equipped => "equipped",
+        }
     }
 }
 
-#[derive(Component)]
-#[require(Name::new("Shield Unit"), EquipmentDisplay, Tooltips::SHIELD_UNIT)]
-pub struct ShieldUnit(pub i32);
+#[derive(Resource, Default)]
+pub struct EquipmentPanel {
+    pub cursor: usize,
+    pub scroll: usize,
+    pub sort: EquipmentSort,
+}
 
-fn shield_unit_display(mut units: Query<(&ShieldUnit, &mut EquipmentDisplay)>) {
-    for (unit, mut display) in units.iter_mut() {
-        display.0 = format!("x{}", unit.0);
+impl EquipmentPanel {
+    fn select(&mut self, cursor: usize, len: usize) {
+        self.cursor = cursor.min(len.saturating_sub(1));
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.cursor + 1 - VISIBLE_ROWS;
+        }
+        self.scroll = self.scroll.min(len.saturating_sub(VISIBLE_ROWS));
     }
 }
 
-#[derive(Component)]
-#[relationship_target(relationship = EquipmentOf)]
-pub struct Equipment(Vec<Entity>);
+type ItemData = (
+    &'static Name,
+    &'static EquipmentDisplay,
+    &'static Tooltips,
+    Option<&'static Modifiers>,
+    Has<Equipped>,
+    Option<&'static Slot>,
+    Option<&'static Rarity>,
+);
 
-#[derive(Component)]
-#[relationship(relationship_target = Equipment)]
-pub struct EquipmentOf(pub Entity);
+fn sorted_equipment(
+    equipment: &Equipment,
+    items: &Query<ItemData>,
+    sort: EquipmentSort,
+) -> Vec<Entity> {
+    let mut entities = equipment.iter().rev().collect::<Vec<_>>();
+    match sort {
+        EquipmentSort::Acquired => {}
+        EquipmentSort::Name => entities.sort_by_cached_key(|e| {
+            items
+                .get(*e)
+                .map(|(name, ..)| name.as_str().to_owned())
+                .unwrap_or_default()
+        }),
+        EquipmentSort::Equipped => {
+            entities.sort_by_key(|e| !items.get(*e).is_ok_and(|(.., equipped, _, _)| equipped))
+        }
+    }
+    entities
+}
 
-#[derive(Default, Component)]
-pub struct EquipmentDisplay(String);
+fn open_equipment_panel(
+    _: On<Start<OpenInventory>>,
+    mut commands: Commands,
+    mut stack: ResMut<PanelStack>,
+    window: Option<Single<(), With<EquipmentWindow>>>,
+) {
+    if window.is_some() {
+        return;
+    }
 
-#[derive(Component)]
-pub struct Tooltips(&'static str);
+    let window = stack.open(&mut commands, EquipmentWindow);
+    commands.spawn((EquipmentTooltip, PanelTileOf(window)));
+}
 
-impl Tooltips {
-    pub const HEALTH_UNIT: Self = Self("Health");
-    pub const SHIELD_UNIT: Self = Self("Shield");
+fn close_equipment_panel(
+    trigger: On<Start<Cancel>>,
+    contexts: Query<(), With<InventoryInput>>,
+    window: Single<Entity, With<EquipmentWindow>>,
+    mut stack: ResMut<PanelStack>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context) {
+        stack.close(&mut commands, *window);
+    }
 }
 
-#[derive(Component)]
-struct EquipmentEntity;
-
-const EQUIPMENT_PANEL: &str = r#"
-+-------------------------------+
-|                               |
-|       E Q U I P M E N T       |
-|                               |
-+-------------------------------+
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-|                               |
-+-------------------------------+
-"#;
+/// Keyboard and gamepad input for [`navigate_equipment_panel`].
+#[derive(Message, Clone, Copy, PartialEq, Eq)]
+enum InventoryCommand {
+    Up,
+    Down,
+    Equip,
+    Drop,
+    Sort,
+}
 
-fn display_equipment(
+fn inventory_command<A: InputAction>(
+    command: InventoryCommand,
+) -> impl Fn(On<Start<A>>, Query<(), With<InventoryInput>>, MessageWriter<InventoryCommand>) {
+    move |trigger, contexts, mut commands| {
+        if contexts.contains(trigger.context) {
+            commands.write(command);
+        }
+    }
+}
+
+/// The inventory actions only fire while the equipment panel has focus.
+fn activate_inventory_input(
     mut commands: Commands,
-    player: Single<&Equipment>,
-    equipment: Query<(&Name, &EquipmentDisplay, &Tooltips)>,
-    entities: Query<Entity, With<EquipmentEntity>>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut is_displayed: Local<bool>,
+    stack: Res<PanelStack>,
+    windows: Query<(), With<EquipmentWindow>>,
+    contexts: Query<Entity, With<InventoryInput>>,
 ) {
-    if input.just_pressed(KeyCode::KeyI) {
-        if *is_displayed {
-            for entity in entities.iter() {
-                commands.entity(entity).despawn();
+    if !stack.is_changed() {
+        return;
+    }
+    let active = panel::focused(stack, windows);
+    input::set_active::<InventoryInput>(&mut commands, contexts, active);
+}
+
+fn navigate_equipment_panel(
+    mut commands: Commands,
+    mut panel: ResMut<EquipmentPanel>,
+    player: Single<(&Equipment, &TilePosition), With<Player>>,
+    items: Query<ItemData>,
+    mut input: MessageReader<InventoryCommand>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut wheel: MessageReader<MouseWheel>,
+    mut moved: MessageReader<CursorMoved>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+) {
+    let (equipment, position) = *player;
+    let entities = sorted_equipment(equipment, &items, panel.sort);
+    let len = entities.len();
+
+    let input = input.read().copied().collect::<Vec<_>>();
+    for command in input.iter() {
+        match command {
+            InventoryCommand::Sort => panel.sort = panel.sort.next(),
+            InventoryCommand::Up => {
+                let cursor = panel.cursor.saturating_sub(1);
+                panel.select(cursor, len);
             }
-        } else {
-            for (tile, position) in text_tiles(EQUIPMENT_PANEL, 0, 0, TextAnchor::Center) {
-                commands.spawn((
-                    tile,
-                    Transform::from_translation(position.extend(10.0)),
-                    EquipmentEntity,
-                ));
+            InventoryCommand::Down => {
+                let cursor = panel.cursor + 1;
+                panel.select(cursor, len);
             }
+            InventoryCommand::Equip | InventoryCommand::Drop => {}
+        }
+    }
+    for event in wheel.read() {
+        let cursor = if event.y > 0.0 {
+            panel.cursor.saturating_sub(1)
+        } else {
+            panel.cursor + 1
+        };
+        panel.select(cursor, len);
+    }
 
-            for (y, (name, display, tooltips)) in equipment.iter_many(player.iter()).enumerate() {
-                for (tile, position) in text_tiles(
-                    &format!("{} - {} - {}", name, display.0, tooltips.0),
-                    -30 / 2,
-                    4 - y as i32,
-                    TextAnchor::TopLeft,
-                ) {
-                    commands.spawn((
-                        tile,
-                        Transform::from_translation(position.extend(15.0)),
-                        EquipmentEntity,
-                    ));
+    // Headless apps have no window to hover with. The panel is drawn in
+    // screen space, centered on the window.
+    let hovered = window
+        .and_then(|window| {
+            let cursor = window.cursor_position()?;
+            Some((cursor - window.size() / 2.0) * Vec2::new(1.0, -1.0))
+        })
+        .map(|screen| (screen / TILE_SIZE as f32).floor().as_ivec2())
+        .and_then(|tile| {
+            let rows = equipment_rows();
+            rows.contains(tile)
+                .then(|| panel.scroll + (rows.max.y - tile.y) as usize)
+        })
+        .filter(|row| *row < len);
+    // Hovering only selects when the pointer moves, so a pointer resting on
+    // the list does not undo keyboard and wheel navigation.
+    let pointer_moved = moved.read().count() > 0;
+    if let Some(row) = hovered
+        && (mouse.just_pressed(MouseButton::Left) || pointer_moved && row != panel.cursor)
+    {
+        panel.select(row, len);
+    }
+
+    // Keep the cursor valid when items are removed from under it.
+    if panel.cursor >= len.max(1) {
+        panel.select(len.saturating_sub(1), len);
+    }
+
+    let Some(&selected) = entities.get(panel.cursor) else {
+        return;
+    };
+    let equip = input.contains(&InventoryCommand::Equip)
+        || hovered.is_some() && mouse.just_pressed(MouseButton::Right);
+    if equip {
+        let Ok((.., equipped, slot, _)) = items.get(selected) else {
+            return;
+        };
+        if equipped {
+            commands.entity(selected).remove::<Equipped>();
+        } else {
+            for other in equipment.iter() {
+                if let Ok((.., true, other_slot, _)) = items.get(other)
+                    && other_slot.is_some()
+                    && other_slot == slot
+                {
+                    commands.entity(other).remove::<Equipped>();
                 }
             }
+            commands.entity(selected).insert(Equipped);
         }
-        *is_displayed = !*is_displayed;
+    } else if input.contains(&InventoryCommand::Drop) {
+        commands
+            .entity(selected)
+            .remove::<(EquipmentOf, Equipped)>()
+            .insert((Dropped, *position));
     }
 }
+
+fn display_equipment(
+    mut canvases: TileCanvases,
+    panel: Res<EquipmentPanel>,
+    stack: Res<PanelStack>,
+    window: Single<(Entity, Ref<EquipmentWindow>)>,
+    tooltip: Single<Entity, With<EquipmentTooltip>>,
+    player: Single<Ref<Equipment>, With<Player>>,
+    items: Query<ItemData>,
+    changed: Query<(), Or<(Changed<EquipmentDisplay>, Added<Equipped>)>>,
+    mut unequipped: RemovedComponents<Equipped>,
+) {
+    let unequipped = unequipped.read().count() > 0;
+    let (window, opened) = window.into_inner();
+    if !opened.is_added()
+        && !panel.is_changed()
+        && !player.is_changed()
+        && changed.is_empty()
+        && !unequipped
+    {
+        return;
+    }
+    let z = stack.z(window, 0.0);
+
+    let entities = sorted_equipment(&player, &items, panel.sort);
+    let lines = entities
+        .iter()
+        .skip(panel.scroll)
+        .take(VISIBLE_ROWS)
+        .filter_map(|entity| items.get(*entity).ok())
+        .map(|(name, display, .., equipped, _, _)| {
+            format!(
+                "{} {} {}",
+                if equipped { '*' } else { ' ' },
+                name,
+                display.0
+            )
+        })
+        .collect::<Vec<_>>();
+    let shown = lines.len() as u32;
+    let selected = panel
+        .cursor
+        .checked_sub(panel.scroll)
+        .filter(|row| *row < lines.len());
+
+    let arrows = if entities.len() > VISIBLE_ROWS {
+        format!(
+            "{}{}",
+            if panel.scroll > 0 { '^' } else { ' ' },
+            if panel.scroll + VISIBLE_ROWS < entities.len() {
+                'v'
+            } else {
+                ' '
+            },
+        )
+    } else {
+        String::new()
+    };
+    let list = Widget::column([
+        Widget::list(lines, selected),
+        Widget::Space(VISIBLE_ROWS as u32 - shown),
+    ]);
+    let canvas =
+        equipment_panel(equipment_header(panel.sort, arrows), list).render(EQUIPMENT_WIDTH);
+    let (left, top) = (-((canvas.size.x / 2) as i32), (canvas.size.y / 2) as i32);
+    // Only the rows that differ from the last draw are updated, so moving
+    // the cursor touches two rows and the tooltip.
+    canvases.draw(window, canvas, Vec3::Z * z, TextAnchor::Center);
+
+    // The tooltip sits right of the panel, one column apart.
+    let corner = IVec2::new(left + EQUIPMENT_WIDTH as i32 + 1, top);
+    let origin = (corner * TILE_SIZE as i32).as_vec2().extend(z);
+    let Some(Ok((name, _, tooltips, modifiers, equipped, slot, rarity))) =
+        entities.get(panel.cursor).map(|e| items.get(*e))
+    else {
+        canvases.draw(
+            *tooltip,
+            Canvas::new(UVec2::ZERO),
+            origin,
+            TextAnchor::TopLeft,
+        );
+        return;
+    };
+
+    let rarity_color = rarity.map_or(StyledText::FG, |r| r.color());
+    let mut rows = vec![
+        Widget::text(name.to_string()),
+        Widget::text(StyledText::new().fg(
+            format!(
+                "{} {}",
+                rarity.map(|r| r.label()).unwrap_or_default(),
+                slot.map(|s| s.label()).unwrap_or_default(),
+            ),
+            rarity_color,
+        )),
+        Widget::text(if equipped { "[equipped]" } else { "" }),
+        Widget::Space(1),
+        Widget::paragraph(tooltips.0.clone()),
+    ];
+    rows.extend(
+        modifiers
+            .into_iter()
+            .flat_map(|m| m.iter().map(|m| Widget::text(m.to_string()))),
+    );
+
+    canvases.draw(
+        *tooltip,
+        Widget::border(Widget::column(rows)).render(TOOLTIP_WIDTH as u32 + 2),
+        origin,
+        TextAnchor::TopLeft,
+    );
+}
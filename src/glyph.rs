@@ -0,0 +1,35 @@
+//! Maps Unicode characters to atlas indices.
+//!
+//! `anno16.png` is laid out like code page 437, so box drawing characters,
+//! card suits and accented letters each have their own glyph.
+
+/// The character drawn by each atlas index, in order.
+pub const CP437: &str = concat!(
+    "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼►◄↕‼¶§▬↨↑↓→←∟↔▲▼",
+    " !\"#$%&'()*+,-./0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmnopqrstuvwxyz{|}~⌂",
+    "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+/// The glyph drawn for characters that are not in the atlas.
+pub const UNKNOWN: u8 = b'?';
+
+/// The atlas index of `c`, or [`UNKNOWN`] if the atlas has no such glyph.
+pub fn glyph(c: char) -> u8 {
+    if (' '..='~').contains(&c) {
+        return c as u8;
+    }
+    CP437
+        .chars()
+        .position(|glyph| glyph == c)
+        .map_or(UNKNOWN, |index| index as u8)
+}
+
+/// The character drawn by an atlas index.
+pub fn char(index: u8) -> char {
+    CP437.chars().nth(index as usize).unwrap_or('?')
+}
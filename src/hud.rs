@@ -0,0 +1,58 @@
+use crate::{
+    TILE_SIZE, bind,
+    bind::Binding,
+    layout::Widget,
+    level::Experience,
+    panel,
+    player::Player,
+    stats::{Health, Stats},
+    text::StyledText,
+};
+use bevy::prelude::*;
+use bevy_query_observer::{AddStartObserver, Start};
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(bind::plugin::<HudData, HudChanged>)
+        .configure_sets(
+            PostUpdate,
+            bind::BindingSystems.after(crate::stats::compute_stats),
+        )
+        .add_start_observer(spawn_hud);
+}
+
+type HudData = (&'static Experience, &'static Health, &'static Stats);
+type HudChanged = Or<(Changed<Experience>, Changed<Health>, Changed<Stats>)>;
+
+/// Tile coordinates of the top left corner of the HUD.
+const HUD_POSITION: IVec2 = IVec2::new(-31, 31);
+/// Width of the HUD in tiles.
+const HUD_WIDTH: u32 = 62;
+
+fn spawn_hud(player: Start<(Entity, &Player)>, mut commands: Commands) {
+    let (player, _) = player.into_inner();
+
+    commands.spawn((
+        Binding::<HudData>::new(player, HUD_WIDTH, hud),
+        // Below every panel, so open panels cover the HUD.
+        Transform::from_translation(
+            (HUD_POSITION * TILE_SIZE as i32)
+                .as_vec2()
+                .extend(panel::BASE_Z - 1.0),
+        ),
+    ));
+}
+
+fn hud((experience, health, stats): (&Experience, &Health, &Stats)) -> Widget<'static> {
+    let hud = format!(
+        "[yellow]LV[/] {}  [cyan]XP[/] {}/{}  [red]HP[/] {}/{}  ATK {}  DEF {}  SPD {}",
+        experience.level,
+        experience.xp,
+        experience.next_threshold(),
+        health.0,
+        stats.max_health,
+        stats.attack,
+        stats.defense,
+        stats.speed,
+    );
+    Widget::text(StyledText::markup(&hud).into_owned())
+}
@@ -1,27 +1,382 @@
-use crate::player::Player;
+use crate::{
+    GameState,
+    controls::{Control, Controls},
+    player::Player,
+};
 use bevy::prelude::*;
 use bevy_enhanced_input::prelude::*;
 
 pub fn plugin(app: &mut App) {
     app.add_input_context::<Player>()
-        .add_observer(inject_bindings);
+        .add_input_context::<ArenaInput>()
+        .add_input_context::<InventoryInput>()
+        .add_input_context::<MenuInput>()
+        .add_input_context::<ControlsInput>()
+        .add_input_context::<LookInput>()
+        .add_observer(inject_bindings)
+        .add_systems(Startup, spawn_contexts)
+        .add_systems(
+            PreUpdate,
+            apply_controls
+                .run_if(resource_changed::<Controls>)
+                .run_if(not(resource_added::<Controls>)),
+        )
+        .add_systems(OnEnter(GameState::LevelUp), activate::<MenuInput>(true))
+        .add_systems(OnExit(GameState::LevelUp), activate::<MenuInput>(false));
 }
 
 #[derive(InputAction)]
 #[action_output(Vec2)]
 pub struct Move;
 
-fn inject_bindings(trigger: On<Insert, Player>, mut commands: Commands) {
-    commands.entity(trigger.entity).insert(actions!(Player[
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct OpenInventory;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct OpenLook;
+
+/// Moves to the next entity on the tile under the look cursor.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct CycleLook;
+
+/// Picks the focused option of a menu.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct Confirm;
+
+/// Backs out of a menu.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct Cancel;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct NavigateUp;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct NavigateDown;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct NavigateLeft;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct NavigateRight;
+
+/// Picks a numbered option directly. The value is the number of the option,
+/// starting from 1.
+#[derive(InputAction)]
+#[action_output(f32)]
+pub struct SelectOption;
+
+impl SelectOption {
+    /// The zero based index of the option picked by `value`.
+    pub fn index(value: f32) -> Option<usize> {
+        let number = value.round() as i32;
+        (number >= 1).then(|| number as usize - 1)
+    }
+}
+
+/// Equips or unequips the selected item.
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct EquipItem;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct DropItem;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct SortItems;
+
+/// Input context of the fight menu, active while the player picks a move and
+/// the arena has focus.
+#[derive(Component)]
+pub struct ArenaInput;
+
+/// Input context of the equipment panel, active while it has focus.
+#[derive(Component)]
+pub struct InventoryInput;
+
+/// Input context of menus like the level up screen.
+#[derive(Component)]
+pub struct MenuInput;
+
+/// Input context of the controls screen, active while it has focus.
+#[derive(Component)]
+pub struct ControlsInput;
+
+/// Input context of look mode, which moves a cursor instead of the player.
+#[derive(Component)]
+pub struct LookInput;
+
+fn inject_bindings(trigger: On<Insert, Player>, controls: Res<Controls>, mut commands: Commands) {
+    commands
+        .entity(trigger.entity)
+        .insert(player_actions(&controls));
+}
+
+fn spawn_contexts(controls: Res<Controls>, mut commands: Commands) {
+    commands.spawn((
+        ArenaInput,
+        ContextActivity::<ArenaInput>::INACTIVE,
+        arena_actions(&controls),
+    ));
+    commands.spawn((
+        InventoryInput,
+        ContextActivity::<InventoryInput>::INACTIVE,
+        inventory_actions(&controls),
+    ));
+    commands.spawn((
+        MenuInput,
+        ContextActivity::<MenuInput>::INACTIVE,
+        menu_actions::<MenuInput>(&controls),
+    ));
+    commands.spawn((
+        ControlsInput,
+        ContextActivity::<ControlsInput>::INACTIVE,
+        controls_actions(&controls),
+    ));
+    commands.spawn((
+        LookInput,
+        ContextActivity::<LookInput>::INACTIVE,
+        look_actions(&controls),
+    ));
+}
+
+/// Replaces the actions of every context after the [`Controls`] change.
+fn apply_controls(
+    controls: Res<Controls>,
+    players: Query<Entity, With<Player>>,
+    arena: Query<Entity, With<ArenaInput>>,
+    inventory: Query<Entity, With<InventoryInput>>,
+    menus: Query<Entity, With<MenuInput>>,
+    controls_screens: Query<Entity, With<ControlsInput>>,
+    looks: Query<Entity, With<LookInput>>,
+    mut commands: Commands,
+) {
+    for entity in players.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<Player>>()
+            .insert(player_actions(&controls));
+    }
+    for entity in arena.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<ArenaInput>>()
+            .insert(arena_actions(&controls));
+    }
+    for entity in inventory.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<InventoryInput>>()
+            .insert(inventory_actions(&controls));
+    }
+    for entity in menus.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<MenuInput>>()
+            .insert(menu_actions::<MenuInput>(&controls));
+    }
+    for entity in controls_screens.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<ControlsInput>>()
+            .insert(controls_actions(&controls));
+    }
+    for entity in looks.iter() {
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<LookInput>>()
+            .insert(look_actions(&controls));
+    }
+}
+
+/// Confirming and cancelling only fire once the key that opened a menu is
+/// released.
+fn once() -> ActionSettings {
+    ActionSettings {
+        require_reset: true,
+        ..Default::default()
+    }
+}
+
+fn buttons(controls: &Controls, bound: &[Control]) -> impl Bundle {
+    Bindings::spawn(SpawnIter(controls.bindings(bound).into_iter()))
+}
+
+/// Binds the move controls and the left stick to a [`Move`] action.
+fn move_bindings(controls: &Controls) -> impl Bundle {
+    // Each direction is bound like in `Cardinal`, turning buttons into axes.
+    let direction = |control| controls.bindings(&[control]).into_iter();
+    let up = direction(Control::MoveUp).map(|binding| (binding, SwizzleAxis::YXZ));
+    let down =
+        direction(Control::MoveDown).map(|binding| (binding, Negate::all(), SwizzleAxis::YXZ));
+    let right = direction(Control::MoveRight);
+    let left = direction(Control::MoveLeft).map(|binding| (binding, Negate::all()));
+
+    Bindings::spawn((
+        SpawnIter(up),
+        SpawnIter(down),
+        SpawnIter(right),
+        SpawnIter(left),
+        Axial::left_stick(),
+    ))
+}
+
+fn player_actions(controls: &Controls) -> impl Bundle {
+    actions!(Player[
         (
             Action::<Move>::new(),
             DeadZone::default(),
             Pulse::new(0.2),
-            Bindings::spawn((
-                Cardinal::wasd_keys(),
-                Cardinal::arrows(),
-                Axial::left_stick(),
-            )),
+            move_bindings(controls),
         ),
-    ]));
+        (
+            Action::<OpenInventory>::new(),
+            buttons(controls, &[Control::OpenInventory]),
+        ),
+        (Action::<OpenLook>::new(), buttons(controls, &[Control::Look])),
+    ])
+}
+
+fn look_actions(controls: &Controls) -> impl Bundle {
+    actions!(LookInput[
+        (
+            Action::<Move>::new(),
+            DeadZone::default(),
+            Pulse::new(0.12),
+            move_bindings(controls),
+        ),
+        (Action::<CycleLook>::new(), buttons(controls, &[Control::CycleLook])),
+        (
+            Action::<Cancel>::new(),
+            once(),
+            buttons(controls, &[Control::Cancel, Control::Look]),
+        ),
+    ])
+}
+
+fn arena_actions(controls: &Controls) -> impl Bundle {
+    actions!(ArenaInput[
+        (Action::<Confirm>::new(), once(), buttons(controls, &[Control::Confirm])),
+        (
+            Action::<NavigateUp>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateUp]),
+        ),
+        (
+            Action::<NavigateDown>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateDown]),
+        ),
+        (Action::<SelectOption>::new(), once(), select_option_bindings(controls)),
+    ])
+}
+
+fn inventory_actions(controls: &Controls) -> impl Bundle {
+    actions!(InventoryInput[
+        (
+            Action::<Cancel>::new(),
+            once(),
+            buttons(controls, &[Control::Cancel, Control::OpenInventory]),
+        ),
+        (
+            Action::<NavigateUp>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateUp]),
+        ),
+        (
+            Action::<NavigateDown>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateDown]),
+        ),
+        (Action::<EquipItem>::new(), once(), buttons(controls, &[Control::EquipItem])),
+        (Action::<DropItem>::new(), buttons(controls, &[Control::DropItem])),
+        (Action::<SortItems>::new(), buttons(controls, &[Control::SortItems])),
+    ])
+}
+
+fn menu_actions<C: Component>(controls: &Controls) -> impl Bundle {
+    actions!(C[
+        (Action::<Confirm>::new(), once(), buttons(controls, &[Control::Confirm])),
+        (Action::<Cancel>::new(), once(), buttons(controls, &[Control::Cancel])),
+        (
+            Action::<NavigateUp>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateUp]),
+        ),
+        (
+            Action::<NavigateDown>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateDown]),
+        ),
+        (Action::<SelectOption>::new(), once(), select_option_bindings(controls)),
+    ])
+}
+
+fn controls_actions(controls: &Controls) -> impl Bundle {
+    actions!(ControlsInput[
+        (Action::<Confirm>::new(), once(), buttons(controls, &[Control::Confirm])),
+        (Action::<Cancel>::new(), once(), buttons(controls, &[Control::Cancel])),
+        (
+            Action::<NavigateUp>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateUp]),
+        ),
+        (
+            Action::<NavigateDown>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateDown]),
+        ),
+        (
+            Action::<NavigateLeft>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateLeft]),
+        ),
+        (
+            Action::<NavigateRight>::new(),
+            Pulse::new(0.15),
+            buttons(controls, &[Control::NavigateRight]),
+        ),
+    ])
+}
+
+/// Binds [`SelectOption`], scaled to the number of each option.
+fn select_option_bindings(controls: &Controls) -> impl Bundle {
+    let bindings = (1..=9)
+        .flat_map(|n| {
+            controls
+                .bindings(&[Control::SelectOption(n)])
+                .into_iter()
+                .map(move |binding| (binding, Scale::splat(n as f32)))
+        })
+        .collect::<Vec<_>>();
+    Bindings::spawn(SpawnIter(bindings.into_iter()))
+}
+
+/// Turns the input context `C` on or off, for use with state transitions.
+pub fn activate<C: Component>(active: bool) -> impl Fn(Commands, Query<Entity, With<C>>) {
+    move |mut commands, contexts| set_active::<C>(&mut commands, contexts, active)
+}
+
+pub fn set_active<C: Component>(
+    commands: &mut Commands,
+    contexts: Query<Entity, With<C>>,
+    active: bool,
+) {
+    for context in contexts.iter() {
+        commands.entity(context).insert(if active {
+            ContextActivity::<C>::ACTIVE
+        } else {
+            ContextActivity::<C>::INACTIVE
+        });
+    }
 }
@@ -0,0 +1,345 @@
+use crate::{
+    equipment::{Dropped, EquipmentDisplay, Tooltips},
+    stats::{Modifier, ModifierKind, Modifiers},
+    tile::TileSprite,
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    color::palettes::tailwind::{BLUE_400, GRAY_300, GREEN_400, PURPLE_400},
+    prelude::*,
+};
+use bevy_query_observer::{AddStartObserver, AddStopObserver, Start, Stop};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<ItemLibrary>()
+        .init_asset::<AffixLibrary>()
+        .register_asset_loader(RonLoader::<ItemLibrary>::default())
+        .register_asset_loader(RonLoader::<AffixLibrary>::default())
+        .add_systems(PreStartup, load_items)
+        .add_systems(PreUpdate, resolve_items)
+        .add_start_observer(ItemGlyph::observe_drop)
+        .add_stop_observer(ItemGlyph::observe_pickup);
+}
+
+/// Loads a RON file directly into an [`Asset`].
+pub struct RonLoader<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for RonLoader<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &Self::Settings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Component,
+    Serialize,
+    Deserialize,
+)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Common => "Common",
+            Self::Uncommon => "Uncommon",
+            Self::Rare => "Rare",
+            Self::Epic => "Epic",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Common => GRAY_300.into(),
+            Self::Uncommon => GREEN_400.into(),
+            Self::Rare => BLUE_400.into(),
+            Self::Epic => PURPLE_400.into(),
+        }
+    }
+
+    /// The number of affixes rolled onto an item of this rarity.
+    pub fn affixes(&self) -> usize {
+        match self {
+            Self::Common => 0,
+            Self::Uncommon => 1,
+            Self::Rare | Self::Epic => 2,
+        }
+    }
+
+    /// Multiplier applied to the additive modifiers of an item's affixes.
+    pub fn affix_scale(&self) -> i32 {
+        match self {
+            Self::Epic => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The equipment slot an item occupies. Only one item per slot can be
+/// [`Equipped`](crate::equipment::Equipped) at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Deserialize)]
+pub enum Slot {
+    Weapon,
+    Shield,
+    Core,
+    Utility,
+}
+
+impl Slot {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Weapon => "Weapon",
+            Self::Shield => "Shield",
+            Self::Core => "Core",
+            Self::Utility => "Utility",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub slot: Slot,
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub tooltip: String,
+}
+
+/// Every item template, keyed by item id.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct ItemLibrary {
+    pub items: BTreeMap<String, ItemTemplate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AffixTemplate {
+    pub kind: AffixKind,
+    pub name: String,
+    /// The slots this affix can roll on. Empty means every slot.
+    #[serde(default)]
+    pub slots: Vec<Slot>,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub tooltip: String,
+}
+
+impl AffixTemplate {
+    pub fn fits(&self, slot: Slot) -> bool {
+        self.slots.is_empty() || self.slots.contains(&slot)
+    }
+}
+
+/// Every affix template, keyed by affix id.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AffixLibrary {
+    pub affixes: BTreeMap<String, AffixTemplate>,
+}
+
+#[derive(Resource)]
+pub struct Items(pub Handle<ItemLibrary>);
+
+#[derive(Resource)]
+pub struct Affixes(pub Handle<AffixLibrary>);
+
+fn load_items(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(Items(server.load("items.ron")));
+    commands.insert_resource(Affixes(server.load("affixes.ron")));
+}
+
+/// An item instance built from the [`ItemTemplate`] with the matching id and
+/// the [`AffixTemplate`]s in `affixes`.
+///
+/// Its name, display, tooltip, modifiers, slot and rarity are filled in once
+/// the [`ItemLibrary`] and [`AffixLibrary`] are loaded.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub base: String,
+    pub rarity: Rarity,
+    pub affixes: Vec<String>,
+}
+
+impl Item {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            rarity: Rarity::Common,
+            affixes: Vec::new(),
+        }
+    }
+}
+
+/// The [`TileSprite`] used when an item is [`Dropped`].
+#[derive(Component, Clone, Copy)]
+pub struct ItemGlyph(pub TileSprite);
+
+impl ItemGlyph {
+    fn observe_drop(data: Start<(Entity, &Self, &Dropped)>, mut commands: Commands) {
+        let (entity, glyph, _) = data.into_inner();
+        commands.entity(entity).insert(glyph.0);
+    }
+
+    fn observe_pickup(data: Stop<(Entity, &Self, &Dropped)>, mut commands: Commands) {
+        let (entity, ..) = data.into_inner();
+        commands.entity(entity).try_remove::<TileSprite>();
+    }
+}
+
+pub(crate) fn resolve_items(
+    mut commands: Commands,
+    items: Query<(Entity, Ref<Item>)>,
+    item_handle: Res<Items>,
+    affix_handle: Res<Affixes>,
+    item_libraries: Res<Assets<ItemLibrary>>,
+    affix_libraries: Res<Assets<AffixLibrary>>,
+    mut item_events: MessageReader<AssetEvent<ItemLibrary>>,
+    mut affix_events: MessageReader<AssetEvent<AffixLibrary>>,
+) {
+    let reloaded = item_events
+        .read()
+        .filter(|event| event.is_added(&item_handle.0) || event.is_modified(&item_handle.0))
+        .count()
+        + affix_events
+            .read()
+            .filter(|event| event.is_added(&affix_handle.0) || event.is_modified(&affix_handle.0))
+            .count()
+        > 0;
+    let (Some(library), Some(affixes)) = (
+        item_libraries.get(&item_handle.0),
+        affix_libraries.get(&affix_handle.0),
+    ) else {
+        return;
+    };
+
+    for (entity, item) in items.iter() {
+        if !reloaded && !item.is_changed() {
+            continue;
+        }
+        if let Some(resolved) = resolve_item(&item, library, affixes) {
+            commands.entity(entity).insert(resolved);
+        }
+    }
+}
+
+/// The components that `item` resolves to, or `None` if its base is unknown.
+pub(crate) fn resolve_item(
+    item: &Item,
+    library: &ItemLibrary,
+    affixes: &AffixLibrary,
+) -> Option<impl Bundle + use<>> {
+    let Some(template) = library.items.get(&item.base) else {
+        error!("unknown item `{}`", item.base);
+        return None;
+    };
+
+    let rarity = item.rarity.max(template.rarity);
+    let mut name = template.name.clone();
+    let mut modifiers = template.modifiers.clone();
+    let mut tooltip = template.tooltip.clone();
+    for id in item.affixes.iter() {
+        let Some(affix) = affixes.affixes.get(id) else {
+            error!("unknown affix `{id}`");
+            continue;
+        };
+
+        name = match affix.kind {
+            AffixKind::Prefix => format!("{} {name}", affix.name),
+            AffixKind::Suffix => format!("{name} {}", affix.name),
+        };
+        modifiers.extend(affix.modifiers.iter().map(|modifier| match modifier.kind {
+            ModifierKind::Add(value) => Modifier::add(modifier.stat, value * rarity.affix_scale()),
+            ModifierKind::Mul(_) => *modifier,
+        }));
+        if !affix.tooltip.is_empty() {
+            tooltip.push(' ');
+            tooltip.push_str(&affix.tooltip);
+        }
+    }
+    let modifiers = merge_modifiers(modifiers);
+
+    Some((
+        Name::new(name),
+        EquipmentDisplay::from_modifiers(&modifiers),
+        Tooltips(tooltip),
+        Modifiers(modifiers),
+        template.slot,
+        rarity,
+        ItemGlyph(TileSprite::new(
+            template.glyph,
+            rarity.color(),
+            Color::BLACK,
+        )),
+    ))
+}
+
+/// Combines modifiers of the same stat and kind into a single modifier.
+fn merge_modifiers(modifiers: Vec<Modifier>) -> Vec<Modifier> {
+    let mut merged = Vec::<Modifier>::with_capacity(modifiers.len());
+    for modifier in modifiers {
+        let existing = merged.iter_mut().find(|m| {
+            m.stat == modifier.stat
+                && core::mem::discriminant(&m.kind) == core::mem::discriminant(&modifier.kind)
+        });
+        match (existing, modifier.kind) {
+            (Some(m), ModifierKind::Add(value)) => {
+                if let ModifierKind::Add(total) = &mut m.kind {
+                    *total += value;
+                }
+            }
+            (Some(m), ModifierKind::Mul(value)) => {
+                if let ModifierKind::Mul(total) = &mut m.kind {
+                    *total *= value;
+                }
+            }
+            (None, _) => merged.push(modifier),
+        }
+    }
+    merged
+}
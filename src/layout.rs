@@ -0,0 +1,368 @@
+use crate::{
+    TILE_SIZE,
+    text::StyledText,
+    tile::{TextAnchor, TileSprite},
+};
+use bevy::{color::palettes::tailwind::GRAY_600, prelude::*};
+
+/// A rectangle of tiles that [`Widget`]s are drawn into.
+pub struct Canvas {
+    pub size: UVec2,
+    /// Cells in row-major order, starting from the top row.
+    pub cells: Vec<TileSprite>,
+}
+
+impl Canvas {
+    /// A canvas filled with blank tiles, so it hides whatever is below it.
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            cells: vec![blank(); (size.x * size.y) as usize],
+        }
+    }
+
+    /// Sets the tile `x` columns from the left and `y` rows from the top.
+    /// Tiles outside the canvas are ignored.
+    pub fn set(&mut self, x: u32, y: u32, tile: TileSprite) {
+        if x < self.size.x && y < self.size.y {
+            self.cells[(y * self.size.x + x) as usize] = tile;
+        }
+    }
+
+    /// Every tile with its position in pixels, laid out like
+    /// [`text_tiles`](crate::tile::text_tiles).
+    pub fn tiles(
+        &self,
+        x: i32,
+        y: i32,
+        anchor: TextAnchor,
+    ) -> impl Iterator<Item = (TileSprite, Vec2)> {
+        let offset = match anchor {
+            TextAnchor::TopLeft => IVec2::new(x, y),
+            TextAnchor::Center => {
+                IVec2::new(x - (self.size.x / 2) as i32, y + (self.size.y / 2) as i32)
+            }
+        };
+        let width = self.size.x.max(1);
+
+        self.cells.iter().enumerate().map(move |(index, tile)| {
+            let cell = IVec2::new((index as u32 % width) as i32, (index as u32 / width) as i32);
+            let position = IVec2::new(offset.x + cell.x, offset.y - cell.y);
+            (*tile, (position * TILE_SIZE as i32).as_vec2())
+        })
+    }
+}
+
+fn blank() -> TileSprite {
+    TileSprite::new(' ', StyledText::FG, StyledText::BG)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A piece of tile UI that is measured and drawn for a given width.
+///
+/// ```ignore
+/// Widget::border(Widget::column([
+///     Widget::centered("S T A T S"),
+///     Widget::Separator,
+///     Widget::paragraph(description),
+///     Widget::progress(health as f32 / max as f32, RED_400.into()),
+/// ]))
+/// .padding(1, 0)
+/// .render(33)
+/// ```
+pub enum Widget<'a> {
+    /// Lines of text, cut off at the width.
+    Text(StyledText<'a>, Align),
+    /// Text that wraps at word boundaries.
+    Paragraph(StyledText<'a>),
+    /// One line per item, with the selected item marked by `>`.
+    List {
+        items: Vec<StyledText<'a>>,
+        selected: Option<usize>,
+    },
+    /// A bar filled in proportion to a value between 0 and 1.
+    Progress { value: f32, fill: Color },
+    /// A horizontal line.
+    Separator,
+    /// Empty rows.
+    Space(u32),
+    /// Children stacked from top to bottom.
+    Column(Vec<Widget<'a>>),
+    /// Children side by side, sharing the width equally with a gap of one
+    /// column between them.
+    Row(Vec<Widget<'a>>),
+    /// A box drawn around the child, with an optional title in the top edge.
+    Border {
+        title: Option<StyledText<'a>>,
+        child: Box<Widget<'a>>,
+    },
+    /// Blank columns on the left and right and blank rows above and below.
+    Padding {
+        x: u32,
+        y: u32,
+        child: Box<Widget<'a>>,
+    },
+}
+
+impl<'a> Widget<'a> {
+    pub fn text(text: impl Into<StyledText<'a>>) -> Self {
+        Self::Text(text.into(), Align::Left)
+    }
+
+    pub fn centered(text: impl Into<StyledText<'a>>) -> Self {
+        Self::Text(text.into(), Align::Center)
+    }
+
+    pub fn paragraph(text: impl Into<StyledText<'a>>) -> Self {
+        Self::Paragraph(text.into())
+    }
+
+    pub fn list(
+        items: impl IntoIterator<Item = impl Into<StyledText<'a>>>,
+        selected: Option<usize>,
+    ) -> Self {
+        Self::List {
+            items: items.into_iter().map(Into::into).collect(),
+            selected,
+        }
+    }
+
+    pub fn progress(value: f32, fill: Color) -> Self {
+        Self::Progress { value, fill }
+    }
+
+    pub fn column(children: impl IntoIterator<Item = Widget<'a>>) -> Self {
+        Self::Column(children.into_iter().collect())
+    }
+
+    pub fn row(children: impl IntoIterator<Item = Widget<'a>>) -> Self {
+        Self::Row(children.into_iter().collect())
+    }
+
+    pub fn border(child: Widget<'a>) -> Self {
+        Self::Border {
+            title: None,
+            child: Box::new(child),
+        }
+    }
+
+    pub fn titled(title: impl Into<StyledText<'a>>, child: Widget<'a>) -> Self {
+        Self::Border {
+            title: Some(title.into()),
+            child: Box::new(child),
+        }
+    }
+
+    pub fn padding(self, x: u32, y: u32) -> Self {
+        Self::Padding {
+            x,
+            y,
+            child: Box::new(self),
+        }
+    }
+
+    /// The number of rows this widget needs at `width`.
+    pub fn height(&self, width: u32) -> u32 {
+        match self {
+            Self::Text(text, _) => text.tile_lines().len() as u32,
+            Self::Paragraph(text) => wrap(text, width).len() as u32,
+            Self::List { items, .. } => items.len() as u32,
+            Self::Progress { .. } | Self::Separator => 1,
+            Self::Space(rows) => *rows,
+            Self::Column(children) => children.iter().map(|c| c.height(width)).sum(),
+            Self::Row(children) => row_widths(children.len(), width)
+                .zip(children)
+                .map(|(width, child)| child.height(width))
+                .max()
+                .unwrap_or(0),
+            Self::Border { child, .. } => child.height(width.saturating_sub(2)) + 2,
+            Self::Padding { x, y, child } => child.height(width.saturating_sub(2 * x)) + 2 * y,
+        }
+    }
+
+    /// Draws the widget into a new [`Canvas`] that is `width` columns wide
+    /// and as tall as the widget needs.
+    pub fn render(&self, width: u32) -> Canvas {
+        let mut canvas = Canvas::new(UVec2::new(width, self.height(width)));
+        self.draw(&mut canvas, UVec2::ZERO, width);
+        canvas
+    }
+
+    /// Draws the widget with its top left corner at `origin`.
+    pub fn draw(&self, canvas: &mut Canvas, origin: UVec2, width: u32) {
+        match self {
+            Self::Text(text, align) => {
+                for (y, line) in text.tile_lines().into_iter().enumerate() {
+                    draw_line(
+                        canvas,
+                        origin + UVec2::new(0, y as u32),
+                        width,
+                        &line,
+                        *align,
+                    );
+                }
+            }
+            Self::Paragraph(text) => {
+                for (y, line) in wrap(text, width).into_iter().enumerate() {
+                    draw_line(
+                        canvas,
+                        origin + UVec2::new(0, y as u32),
+                        width,
+                        &line,
+                        Align::Left,
+                    );
+                }
+            }
+            Self::List { items, selected } => {
+                for (y, item) in items.iter().enumerate() {
+                    let marker = if *selected == Some(y) { '>' } else { ' ' };
+                    let mut line = vec![TileSprite::new(marker, StyledText::FG, StyledText::BG)];
+                    line.extend(item.tile_lines().into_iter().next().unwrap_or_default());
+                    draw_line(
+                        canvas,
+                        origin + UVec2::new(0, y as u32),
+                        width,
+                        &line,
+                        Align::Left,
+                    );
+                }
+            }
+            Self::Progress { value, fill } => {
+                let filled = (value.clamp(0.0, 1.0) * width as f32).round() as u32;
+                for x in 0..width {
+                    let tile = if x < filled {
+                        TileSprite::new('█', *fill, StyledText::BG)
+                    } else {
+                        TileSprite::new('░', GRAY_600.into(), StyledText::BG)
+                    };
+                    canvas.set(origin.x + x, origin.y, tile);
+                }
+            }
+            Self::Separator => {
+                for x in 0..width {
+                    canvas.set(origin.x + x, origin.y, line_tile('─'));
+                }
+            }
+            Self::Space(_) => {}
+            Self::Column(children) => {
+                let mut y = origin.y;
+                for child in children {
+                    child.draw(canvas, UVec2::new(origin.x, y), width);
+                    y += child.height(width);
+                }
+            }
+            Self::Row(children) => {
+                let mut x = origin.x;
+                for (child_width, child) in row_widths(children.len(), width).zip(children) {
+                    child.draw(canvas, UVec2::new(x, origin.y), child_width);
+                    x += child_width + 1;
+                }
+            }
+            Self::Border { title, child } => {
+                let height = self.height(width);
+                if width < 2 || height < 2 {
+                    return;
+                }
+                let (right, bottom) = (origin.x + width - 1, origin.y + height - 1);
+                for x in origin.x + 1..right {
+                    canvas.set(x, origin.y, line_tile('─'));
+                    canvas.set(x, bottom, line_tile('─'));
+                }
+                for y in origin.y + 1..bottom {
+                    canvas.set(origin.x, y, line_tile('│'));
+                    canvas.set(right, y, line_tile('│'));
+                }
+                canvas.set(origin.x, origin.y, line_tile('┌'));
+                canvas.set(right, origin.y, line_tile('┐'));
+                canvas.set(origin.x, bottom, line_tile('└'));
+                canvas.set(right, bottom, line_tile('┘'));
+
+                if let Some(title) = title {
+                    let line = title.tile_lines().into_iter().next().unwrap_or_default();
+                    draw_line(
+                        canvas,
+                        origin + UVec2::new(2, 0),
+                        width.saturating_sub(4),
+                        &line,
+                        Align::Left,
+                    );
+                }
+                child.draw(canvas, origin + UVec2::ONE, width - 2);
+            }
+            Self::Padding { x, y, child } => {
+                child.draw(
+                    canvas,
+                    origin + UVec2::new(*x, *y),
+                    width.saturating_sub(2 * x),
+                );
+            }
+        }
+    }
+}
+
+fn line_tile(c: char) -> TileSprite {
+    TileSprite::new(c, StyledText::FG, StyledText::BG)
+}
+
+fn draw_line(canvas: &mut Canvas, origin: UVec2, width: u32, line: &[TileSprite], align: Align) {
+    let len = (line.len() as u32).min(width);
+    let offset = match align {
+        Align::Left => 0,
+        Align::Center => (width - len) / 2,
+        Align::Right => width - len,
+    };
+    for (x, tile) in line.iter().take(len as usize).enumerate() {
+        canvas.set(origin.x + offset + x as u32, origin.y, *tile);
+    }
+}
+
+fn row_widths(count: usize, width: u32) -> impl Iterator<Item = u32> {
+    let count = count as u32;
+    let available = width.saturating_sub(count.saturating_sub(1));
+    let each = available / count.max(1);
+    let extra = available - each * count;
+    (0..count).map(move |i| if i + 1 == count { each + extra } else { each })
+}
+
+/// Splits text into lines no longer than `width`, breaking at spaces where
+/// possible. The spaces kept between words keep their colors.
+fn wrap(text: &StyledText, width: u32) -> Vec<Vec<TileSprite>> {
+    let width = width.max(1) as usize;
+    let space = crate::glyph::glyph(' ');
+
+    let mut lines = Vec::new();
+    for paragraph in text.tile_lines() {
+        let mut line: Vec<TileSprite> = Vec::new();
+        let mut start = 0usize;
+        for word in paragraph.split(|tile| tile.ascii == space) {
+            // The space in front of the word, if any.
+            let gap = start.checked_sub(1).map(|i| paragraph[i]);
+            start += word.len() + 1;
+            if word.is_empty() {
+                continue;
+            }
+            if !line.is_empty() && line.len() + 1 + word.len() > width {
+                lines.push(core::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(gap.unwrap_or_else(blank));
+            }
+            // Words longer than a line are broken up.
+            for tile in word {
+                if line.len() == width {
+                    lines.push(core::mem::take(&mut line));
+                }
+                line.push(*tile);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,281 @@
+use crate::{
+    GameState,
+    arena::{BattleTarget, Death},
+    bind::{TileCanvas, TileCanvases},
+    input::{Confirm, MenuInput, NavigateDown, NavigateUp, SelectOption},
+    layout::Widget,
+    panel::{Modal, PanelStack},
+    player::Player,
+    stats::{Health, Modifier, Modifiers, Stat, Stats, compute_stats},
+    tile::TextAnchor,
+};
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<LevelUpMenu>()
+        .add_systems(OnEnter(GameState::LevelUp), open_level_up)
+        .add_systems(Update, redraw_level_up.run_if(in_state(GameState::LevelUp)))
+        .add_systems(OnExit(GameState::LevelUp), despawn_level_up)
+        .add_observer(navigate_level_up::<NavigateUp>(-1))
+        .add_observer(navigate_level_up::<NavigateDown>(1))
+        .add_observer(confirm_level_up)
+        .add_observer(select_level_up)
+        .add_observer(choose_level_up)
+        .add_observer(award_experience)
+        .add_systems(
+            PostUpdate,
+            heal_after_level_up
+                .after(compute_stats)
+                .before(crate::bind::BindingSystems),
+        );
+}
+
+/// Experience gained by defeating an entity.
+#[derive(Component, Clone, Copy)]
+pub struct XpReward(pub u32);
+
+#[derive(Component, Debug, Clone, Copy, Serialize, Deserialize)]
+#[require(Modifiers)]
+pub struct Experience {
+    pub level: u32,
+    pub xp: u32,
+    /// Level ups that have not been spent on the level up screen yet.
+    pub pending: u32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            xp: 0,
+            pending: 0,
+        }
+    }
+}
+
+impl Experience {
+    /// Experience required to advance from `level` to the next level.
+    pub fn threshold(level: u32) -> u32 {
+        10 * level
+    }
+
+    pub fn next_threshold(&self) -> u32 {
+        Self::threshold(self.level)
+    }
+
+    /// Adds `xp` and returns the number of levels gained.
+    pub fn gain(&mut self, xp: u32) -> u32 {
+        self.xp += xp;
+
+        let mut gained = 0;
+        while self.xp >= self.next_threshold() {
+            self.xp -= self.next_threshold();
+            self.level += 1;
+            gained += 1;
+        }
+        self.pending += gained;
+        gained
+    }
+}
+
+/// Modifier granted automatically for every level gained.
+const LEVEL_GROWTH: Modifier = Modifier::add(Stat::MaxHealth, 2);
+
+fn award_experience(
+    trigger: On<Death>,
+    rewards: Query<&XpReward, With<BattleTarget>>,
+    player: Single<(&mut Experience, &mut Modifiers), With<Player>>,
+) {
+    let Ok(reward) = rewards.get(trigger.entity) else {
+        return;
+    };
+
+    let (mut experience, mut modifiers) = player.into_inner();
+    let gained = experience.gain(reward.0);
+    info!("gained {} xp", reward.0);
+    for _ in 0..gained {
+        info!("reached level {}", experience.level);
+        modifiers.push(LEVEL_GROWTH);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LevelUpChoice {
+    label: &'static str,
+    modifier: Modifier,
+}
+
+const CHOICES: [LevelUpChoice; 5] = [
+    LevelUpChoice {
+        label: "1. STRENGTH   +1 ATK",
+        modifier: Modifier::add(Stat::Attack, 1),
+    },
+    LevelUpChoice {
+        label: "2. PLATING    +1 DEF",
+        modifier: Modifier::add(Stat::Defense, 1),
+    },
+    LevelUpChoice {
+        label: "3. SERVOS     +1 SPD",
+        modifier: Modifier::add(Stat::Speed, 1),
+    },
+    LevelUpChoice {
+        label: "4. CAPACITY   +5 HP",
+        modifier: Modifier::add(Stat::MaxHealth, 5),
+    },
+    LevelUpChoice {
+        label: "5. OVERDRIVE +15% ATK",
+        modifier: Modifier::mul(Stat::Attack, 1.15),
+    },
+];
+
+#[derive(Component)]
+#[require(Modal, TileCanvas)]
+struct LevelUpWindow;
+
+/// The choice highlighted on the level up screen.
+#[derive(Resource, Default)]
+struct LevelUpMenu {
+    selected: usize,
+}
+
+/// Picks the level up choice at `index` in [`CHOICES`].
+#[derive(Event)]
+struct ChooseLevelUp {
+    index: usize,
+}
+
+/// Width of the level up panel in tiles.
+const LEVEL_UP_WIDTH: u32 = 33;
+
+fn open_level_up(
+    mut commands: Commands,
+    mut stack: ResMut<PanelStack>,
+    mut menu: ResMut<LevelUpMenu>,
+) {
+    *menu = LevelUpMenu::default();
+    stack.open(&mut commands, LevelUpWindow);
+}
+
+fn redraw_level_up(
+    mut canvases: TileCanvases,
+    stack: Res<PanelStack>,
+    menu: Res<LevelUpMenu>,
+    player: Single<Ref<Experience>, With<Player>>,
+    window: Single<(Entity, Ref<LevelUpWindow>)>,
+) {
+    let (window, opened) = window.into_inner();
+    if !opened.is_added() && !menu.is_changed() && !player.is_changed() {
+        return;
+    }
+
+    let experience = &*player;
+    let selected = menu.selected;
+    let panel = Widget::border(
+        Widget::column([
+            Widget::Space(1),
+            Widget::centered("L E V E L   U P"),
+            Widget::Space(1),
+            Widget::Separator,
+            Widget::Space(1),
+            Widget::text(format!(
+                "Level {}! {} upgrade(s) left:",
+                experience.level, experience.pending
+            )),
+            Widget::Space(1),
+            Widget::list(CHOICES.iter().map(|choice| choice.label), Some(selected)),
+            Widget::Space(1),
+        ])
+        .padding(1, 0),
+    );
+
+    canvases.draw(
+        window,
+        panel.render(LEVEL_UP_WIDTH),
+        Vec3::Z * stack.z(window, 0.0),
+        TextAnchor::Center,
+    );
+}
+
+fn despawn_level_up(
+    mut commands: Commands,
+    mut stack: ResMut<PanelStack>,
+    windows: Query<Entity, With<LevelUpWindow>>,
+) {
+    for window in windows.iter() {
+        stack.close(&mut commands, window);
+    }
+}
+
+fn navigate_level_up<A: InputAction>(
+    step: isize,
+) -> impl Fn(On<Start<A>>, Query<(), With<MenuInput>>, ResMut<LevelUpMenu>) {
+    move |trigger, contexts, mut menu| {
+        if contexts.contains(trigger.context) {
+            menu.selected = menu
+                .selected
+                .saturating_add_signed(step)
+                .min(CHOICES.len() - 1);
+        }
+    }
+}
+
+fn confirm_level_up(
+    trigger: On<Start<Confirm>>,
+    contexts: Query<(), With<MenuInput>>,
+    menu: Res<LevelUpMenu>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context) {
+        commands.trigger(ChooseLevelUp {
+            index: menu.selected,
+        });
+    }
+}
+
+fn select_level_up(
+    trigger: On<Start<SelectOption>>,
+    contexts: Query<(), With<MenuInput>>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context)
+        && let Some(index) = SelectOption::index(trigger.value)
+        && index < CHOICES.len()
+    {
+        commands.trigger(ChooseLevelUp { index });
+    }
+}
+
+/// Heals to full once the [`Stats`] include the chosen upgrade, so a higher
+/// max health is filled up too.
+#[derive(Component)]
+struct LevelUpHeal;
+
+fn heal_after_level_up(
+    mut commands: Commands,
+    mut healed: Query<(Entity, &mut Health, &Stats), With<LevelUpHeal>>,
+) {
+    for (entity, mut health, stats) in healed.iter_mut() {
+        health.0 = stats.max_health;
+        commands.entity(entity).remove::<LevelUpHeal>();
+    }
+}
+
+fn choose_level_up(
+    trigger: On<ChooseLevelUp>,
+    player: Single<(Entity, &mut Experience, &mut Modifiers), With<Player>>,
+    mut commands: Commands,
+) {
+    let (player, mut experience, mut modifiers) = player.into_inner();
+
+    let choice = CHOICES[trigger.index];
+    info!("chose {}", choice.modifier);
+    modifiers.push(choice.modifier);
+    commands.entity(player).insert(LevelUpHeal);
+
+    experience.pending = experience.pending.saturating_sub(1);
+    if experience.pending == 0 {
+        commands.set_state(GameState::Overworld);
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use bevy_enhanced_input::EnhancedInputPlugin;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub mod arena;
+pub mod bind;
+pub mod controls;
+pub mod enemy;
+pub mod equipment;
+pub mod glyph;
+pub mod hud;
+pub mod input;
+pub mod item;
+pub mod layout;
+pub mod level;
+pub mod look;
+pub mod loot;
+pub mod mapgen;
+pub mod observer;
+pub mod panel;
+pub mod picking;
+pub mod player;
+#[cfg(feature = "term_renderer")]
+pub mod render;
+pub mod save;
+pub mod screenshot;
+pub mod snapshot;
+pub mod sprite;
+pub mod stats;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod text;
+pub mod tile;
+pub mod tileset;
+pub mod travel;
+pub mod view;
+
+pub const WIDTH: usize = 1024;
+pub const HEIGHT: usize = 1024;
+pub const TILE_SIZE: usize = 16;
+
+/// Game logic: tiles, movement, map generation, combat, items and enemies.
+///
+/// Does not require a window, renderer or audio, so it can run on top of
+/// [`MinimalPlugins`]. See [`headless_app`].
+pub struct GameplayPlugin {
+    pub seed: u64,
+    /// Where the controls and saves are read and written. See [`DataDir`].
+    pub data_dir: PathBuf,
+}
+
+impl Default for GameplayPlugin {
+    fn default() -> Self {
+        Self {
+            seed: 69,
+            data_dir: PathBuf::from("."),
+        }
+    }
+}
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DataDir(self.data_dir.clone()));
+        app.add_plugins((
+            bevy_rand::prelude::EntropyPlugin::<bevy_rand::prelude::WyRand>::with_seed(
+                self.seed.to_le_bytes(),
+            ),
+            EnhancedInputPlugin,
+            controls::plugin,
+            travel::plugin,
+            look::plugin,
+            screenshot::plugin,
+        ))
+        .add_plugins((
+            tile::plugin,
+            mapgen::plugin,
+            input::plugin,
+            enemy::plugin,
+            arena::plugin,
+            player::plugin,
+            equipment::plugin,
+            panel::plugin,
+            item::plugin,
+            loot::plugin,
+            stats::plugin,
+            level::plugin,
+            save::plugin,
+            view::plugin,
+        ))
+        .init_state::<GameState>()
+        // The arena can still be left with the toggle while it is the only
+        // panel, but not from a screen on top of it.
+        .add_systems(
+            Update,
+            enter_exit_arena.run_if(panel::no_modal.or(panel::focused::<arena::ArenaWindow>)),
+        );
+    }
+}
+
+/// Draws the tile world with Bevy sprites, along with the HUD.
+pub struct PresentationPlugin;
+
+impl Plugin for PresentationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((sprite::plugin, picking::plugin, hud::plugin));
+    }
+}
+
+/// The directory of the [`controls`] and [`save`] files.
+#[derive(Resource, Debug, Clone)]
+pub struct DataDir(pub PathBuf);
+
+impl Default for DataDir {
+    fn default() -> Self {
+        Self(PathBuf::from("."))
+    }
+}
+
+/// Builds an [`App`] that runs the [`GameplayPlugin`] without a window, for
+/// integration tests and batch simulations.
+///
+/// Controls and saves are kept in a new temporary directory, so the files of
+/// the working directory are neither read nor overwritten.
+pub fn headless_app(seed: u64) -> App {
+    static APPS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let app = APPS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let data_dir = std::env::temp_dir().join(format!("dung-{}-{app}", std::process::id()));
+    headless_app_in(seed, data_dir)
+}
+
+/// Like [`headless_app`], but with the controls and saves in `data_dir`,
+/// which is created if it is missing.
+pub fn headless_app_in(seed: u64, data_dir: impl Into<PathBuf>) -> App {
+    let data_dir = data_dir.into();
+    if let Err(e) = std::fs::create_dir_all(&data_dir) {
+        warn!("failed to create {}: {e}", data_dir.display());
+    }
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        bevy::input::InputPlugin,
+        bevy::state::app::StatesPlugin,
+        GameplayPlugin { seed, data_dir },
+    ));
+    // `App::run` would do this, but tests call `App::update` directly.
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States, Serialize, Deserialize)]
+pub enum GameState {
+    #[default]
+    Overworld,
+    Arena,
+    LevelUp,
+}
+
+fn enter_exit_arena(
+    mut commands: Commands,
+    controls: Res<controls::Controls>,
+    input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut in_arena: Local<bool>,
+) {
+    if controls.just_pressed(controls::Control::ToggleArena, &input, &gamepads) {
+        if *in_arena {
+            commands.set_state(GameState::Overworld);
+        } else {
+            commands.set_state(GameState::Arena);
+        }
+        *in_arena = !*in_arena;
+    }
+}
+
+#[cfg(not(feature = "debug"))]
+pub fn name(_: impl Into<std::borrow::Cow<'static, str>>) -> () {}
+#[cfg(feature = "debug")]
+pub fn name(name: impl Into<std::borrow::Cow<'static, str>>) -> Name {
+    Name::new(name)
+}
@@ -0,0 +1,280 @@
+//! Look mode: a cursor moved with the keyboard that describes everything on
+//! the tile under it.
+
+use crate::{
+    TILE_SIZE,
+    bind::{BoundTileOf, TileCanvas, TileCanvases},
+    equipment::{Equipment, Equipped, Tooltips},
+    input::{self, Cancel, CycleLook, LookInput, Move, OpenLook},
+    layout::{Canvas, Widget},
+    panel::{self, Modal, PanelStack, PanelTileOf},
+    picking::{DescribeData, describe},
+    player::Player,
+    stats::{Health, Stats},
+    text::StyledText,
+    tile::{TextAnchor, TileIndex, TilePosition, TileSprite, TileZ},
+    view::Viewport,
+};
+use bevy::{
+    color::palettes::tailwind::{GRAY_400, RED_400, YELLOW_300},
+    prelude::*,
+};
+use bevy_enhanced_input::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<LookCursor>()
+        .add_observer(open_look)
+        .add_observer(close_look)
+        .add_observer(move_cursor)
+        .add_observer(cycle_look)
+        .add_systems(Update, redraw_look.run_if(any_with_component::<LookWindow>))
+        .add_systems(PostUpdate, activate_look_input);
+}
+
+/// Width of the description panel in tiles.
+const LOOK_WIDTH: u32 = 30;
+
+/// The [`Panel`](crate::panel::Panel) of look mode.
+#[derive(Component)]
+#[require(Modal, TileCanvas)]
+struct LookWindow;
+
+/// The highlighted tile under the cursor, drawn above the [`LookWindow`].
+#[derive(Component)]
+#[require(TileCanvas)]
+struct LookHighlight;
+
+/// The examined tile, and which of the entities stacked on it is described.
+#[derive(Resource, Default)]
+struct LookCursor {
+    position: IVec2,
+    index: usize,
+}
+
+/// The entities on `position`, from the top to the bottom of the stack.
+fn stacked(position: IVec2, index: &TileIndex, z: &Query<&TileZ>) -> Vec<Entity> {
+    let mut entities = index
+        .get(&TilePosition(position))
+        .cloned()
+        .unwrap_or_default();
+    entities.sort_by_key(|entity| std::cmp::Reverse(z.get(*entity).map_or(0, |z| z.0)));
+    entities
+}
+
+fn open_look(
+    _: On<Start<OpenLook>>,
+    mut commands: Commands,
+    mut stack: ResMut<PanelStack>,
+    mut cursor: ResMut<LookCursor>,
+    player: Single<&TilePosition, With<Player>>,
+    window: Option<Single<(), With<LookWindow>>>,
+) {
+    if window.is_some() {
+        return;
+    }
+
+    *cursor = LookCursor {
+        position: player.0,
+        index: 0,
+    };
+    let window = stack.open(&mut commands, LookWindow);
+    commands.spawn((LookHighlight, PanelTileOf(window)));
+}
+
+fn close_look(
+    trigger: On<Start<Cancel>>,
+    contexts: Query<(), With<LookInput>>,
+    window: Single<Entity, With<LookWindow>>,
+    mut stack: ResMut<PanelStack>,
+    mut commands: Commands,
+) {
+    if contexts.contains(trigger.context) {
+        stack.close(&mut commands, *window);
+    }
+}
+
+fn move_cursor(
+    trigger: On<Fire<Move>>,
+    contexts: Query<(), With<LookInput>>,
+    viewport: Res<Viewport>,
+    mut cursor: ResMut<LookCursor>,
+) {
+    if !contexts.contains(trigger.context) {
+        return;
+    }
+
+    let step = IVec2::new(
+        trigger.value.x.round() as i32,
+        trigger.value.y.round() as i32,
+    );
+    let position = cursor.position + step;
+    // The cursor can not leave the screen, since nothing is drawn outside it.
+    if step != IVec2::ZERO && viewport.contains(position) {
+        *cursor = LookCursor { position, index: 0 };
+    }
+}
+
+fn cycle_look(
+    trigger: On<Start<CycleLook>>,
+    contexts: Query<(), With<LookInput>>,
+    index: Res<TileIndex>,
+    z: Query<&TileZ>,
+    mut cursor: ResMut<LookCursor>,
+) {
+    if !contexts.contains(trigger.context) {
+        return;
+    }
+
+    let len = stacked(cursor.position, &index, &z).len();
+    if len > 0 {
+        cursor.index = (cursor.index + 1) % len;
+    }
+}
+
+fn activate_look_input(
+    mut commands: Commands,
+    stack: Res<PanelStack>,
+    windows: Query<(), With<LookWindow>>,
+    contexts: Query<Entity, With<LookInput>>,
+) {
+    if !stack.is_changed() {
+        return;
+    }
+    let active = panel::focused(stack, windows);
+    input::set_active::<LookInput>(&mut commands, contexts, active);
+}
+
+/// Changes to an examined entity that alter its description.
+type DetailChanged = Or<(
+    Changed<Health>,
+    Changed<Stats>,
+    Changed<Equipment>,
+    Changed<Tooltips>,
+)>;
+
+/// Health, equipment and tooltips of an examined entity.
+type DetailData = (
+    Option<&'static Health>,
+    Option<&'static Stats>,
+    Option<&'static Equipment>,
+    Option<&'static Tooltips>,
+    Option<&'static TileSprite>,
+);
+
+fn redraw_look(
+    mut canvases: TileCanvases,
+    stack: Res<PanelStack>,
+    cursor: Res<LookCursor>,
+    index: Res<TileIndex>,
+    viewport: Res<Viewport>,
+    window: Single<Entity, With<LookWindow>>,
+    highlight: Single<Entity, With<LookHighlight>>,
+    z: Query<&TileZ>,
+    names: Query<DescribeData>,
+    details: Query<DetailData, Without<BoundTileOf>>,
+    changed: Query<(), DetailChanged>,
+    equipped: Query<&Name, With<Equipped>>,
+) {
+    let window = *window;
+
+    let entities = stacked(cursor.position, &index, &z);
+    let selected_index = cursor.index.min(entities.len().saturating_sub(1));
+    let selected = entities.get(selected_index);
+
+    let selected_changed = selected.is_some_and(|entity| changed.contains(*entity));
+    if !cursor.is_changed() && !index.is_changed() && !selected_changed {
+        return;
+    }
+
+    // The cursor shows the glyph on top of the tile, highlighted.
+    let glyph = entities
+        .iter()
+        .find_map(|entity| details.get(*entity).ok().and_then(|detail| detail.4))
+        .map_or(b' ', |sprite| sprite.ascii);
+    let screen = cursor.position - viewport.center;
+    let mut cursor_tile = Canvas::new(UVec2::ONE);
+    cursor_tile.set(
+        0,
+        0,
+        TileSprite {
+            ascii: glyph,
+            fg: Color::BLACK,
+            bg: YELLOW_300.into(),
+        },
+    );
+    canvases.draw(
+        *highlight,
+        cursor_tile,
+        (screen * TILE_SIZE as i32)
+            .as_vec2()
+            .extend(stack.z(window, 1.0)),
+        TextAnchor::TopLeft,
+    );
+
+    let mut lines = vec![Widget::text(
+        StyledText::new()
+            .push(format!("{}, {}", cursor.position.x, cursor.position.y))
+            .fg(
+                match selected {
+                    Some(_) => format!("  {}/{}", selected_index + 1, entities.len()),
+                    None => String::new(),
+                },
+                GRAY_400.into(),
+            ),
+    )];
+
+    if let Some(&entity) = selected {
+        lines.push(Widget::Separator);
+        if let Some(label) = names.get(entity).ok().and_then(describe) {
+            lines.push(Widget::text(label));
+        }
+        if let Ok((health, stats, equipment, tooltips, _)) = details.get(entity) {
+            if let (Some(health), Some(stats)) = (health, stats) {
+                let value = health.0 as f32 / stats.max_health.max(1) as f32;
+                lines.push(Widget::progress(value, RED_400.into()));
+            }
+            if let Some(equipment) = equipment {
+                let items = equipment
+                    .iter()
+                    .filter_map(|item| equipped.get(item).ok())
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>();
+                if !items.is_empty() {
+                    lines.push(Widget::paragraph(format!("Wields {}", items.join(", "))));
+                }
+            }
+            if let Some(tooltips) = tooltips
+                && !tooltips.0.is_empty()
+            {
+                lines.push(Widget::paragraph(tooltips.0.clone()));
+            }
+        }
+    }
+
+    if entities.len() > 1 {
+        lines.push(Widget::Separator);
+        let labels = entities
+            .iter()
+            .map(|entity| {
+                names
+                    .get(*entity)
+                    .ok()
+                    .and_then(describe)
+                    .unwrap_or_else(|| StyledText::new().push("?"))
+            })
+            .collect::<Vec<_>>();
+        lines.push(Widget::list(labels, Some(selected_index)));
+    }
+
+    // In the bottom left corner of the screen.
+    let panel = Widget::titled("LOOK", Widget::column(lines).padding(1, 0)).render(LOOK_WIDTH);
+    let corner = -(viewport.size / 2).as_ivec2() + IVec2::new(1, panel.size.y as i32);
+    canvases.draw(
+        window,
+        panel,
+        (corner * TILE_SIZE as i32)
+            .as_vec2()
+            .extend(stack.z(window, 0.0)),
+        TextAnchor::TopLeft,
+    );
+}
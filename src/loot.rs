@@ -0,0 +1,223 @@
+use crate::{
+    arena::Death,
+    equipment::{Dropped, Equipment, EquipmentOf, Equipped},
+    item::{AffixKind, AffixLibrary, Affixes, Item, ItemLibrary, Items, Rarity, resolve_items},
+    mapgen::Depth,
+    player::Player,
+    tile::{PositionQuery, TilePosition},
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_query_observer::{AddStartObserver, Start};
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use rand::{Rng, seq::IteratorRandom};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PreUpdate, roll_loot.before(resolve_items))
+        .add_observer(drop_loot)
+        .add_start_observer(pick_up_loot);
+}
+
+/// Describes what an entity drops when it dies.
+#[derive(Component, Clone, Copy)]
+pub struct LootTable {
+    /// Chance for each piece of carried [`Equipment`] to drop.
+    pub carried: f64,
+    /// Number of extra items rolled.
+    pub rolls: u32,
+    /// Chance for each extra roll to produce an item.
+    pub chance: f64,
+    /// Base item ids the extra rolls choose from, or any item if empty.
+    pub bases: &'static [&'static str],
+}
+
+impl LootTable {
+    pub const DROID: Self = Self {
+        carried: 0.5,
+        rolls: 1,
+        chance: 0.35,
+        bases: &[],
+    };
+}
+
+fn drop_loot(
+    trigger: On<Death>,
+    dead: Query<(&TilePosition, &LootTable, Option<&Equipment>)>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    mut commands: Commands,
+) {
+    let Ok((&position, table, equipment)) = dead.get(trigger.entity) else {
+        return;
+    };
+
+    for item in equipment.into_iter().flat_map(|e| e.iter()) {
+        if rng.random_bool(table.carried) {
+            commands
+                .entity(item)
+                .remove::<(EquipmentOf, Equipped)>()
+                .insert((Dropped, position));
+        } else {
+            commands.entity(item).despawn();
+        }
+    }
+
+    for _ in 0..table.rolls {
+        if !rng.random_bool(table.chance) {
+            continue;
+        }
+
+        let base = if table.bases.is_empty() {
+            None
+        } else {
+            table.bases.iter().choose(&mut **rng).map(|b| b.to_string())
+        };
+        commands.spawn((LootRoll { base }, Dropped, position));
+    }
+}
+
+fn pick_up_loot(
+    data: Start<(Entity, &TilePosition, &Player)>,
+    dropped: PositionQuery<Entity, (With<Dropped>, With<Item>)>,
+    mut commands: Commands,
+) {
+    let (player, position, _) = data.into_inner();
+
+    for item in dropped.iter(position) {
+        info!("picked up an item");
+        commands
+            .entity(item)
+            .remove::<(Dropped, TilePosition)>()
+            .insert(EquipmentOf(player));
+    }
+}
+
+/// An item that is rolled by the [`LootGenerator`] once the item assets are
+/// loaded, after which it is replaced with an [`Item`].
+#[derive(Component, Default, Clone)]
+pub struct LootRoll {
+    /// The item id to roll affixes for, or any base item if `None`.
+    pub base: Option<String>,
+}
+
+impl LootRoll {
+    pub fn any() -> Self {
+        Self { base: None }
+    }
+}
+
+fn roll_loot(
+    mut commands: Commands,
+    rolls: Query<(Entity, &LootRoll)>,
+    mut generator: LootGenerator,
+) {
+    if rolls.is_empty() || !generator.is_ready() {
+        return;
+    }
+
+    for (entity, roll) in rolls.iter() {
+        let mut entity = commands.entity(entity);
+        entity.remove::<LootRoll>();
+        match generator.roll(roll.base.as_deref()) {
+            Some(item) => {
+                entity.insert(item);
+            }
+            None => {
+                entity.despawn();
+            }
+        }
+    }
+}
+
+/// Rolls random items with rarity tiers and affixes that scale with the
+/// dungeon [`Depth`].
+#[derive(SystemParam)]
+pub struct LootGenerator<'w, 's> {
+    rng: Single<'w, 's, &'static mut WyRand, With<GlobalRng>>,
+    depth: Res<'w, Depth>,
+    items: Res<'w, Items>,
+    affixes: Res<'w, Affixes>,
+    item_libraries: Res<'w, Assets<ItemLibrary>>,
+    affix_libraries: Res<'w, Assets<AffixLibrary>>,
+}
+
+impl LootGenerator<'_, '_> {
+    pub fn is_ready(&self) -> bool {
+        self.item_libraries.contains(&self.items.0)
+            && self.affix_libraries.contains(&self.affixes.0)
+    }
+
+    pub fn rarity(&mut self) -> Rarity {
+        let depth = self.depth.0;
+        let weights = [
+            (Rarity::Common, 100),
+            (Rarity::Uncommon, 20 + 10 * depth),
+            (Rarity::Rare, 5 * depth),
+            (Rarity::Epic, 2 * depth.saturating_sub(2)),
+        ];
+
+        let total = weights.iter().map(|(_, w)| w).sum::<u32>();
+        let mut roll = self.rng.random_range(0..total);
+        for (rarity, weight) in weights {
+            if roll < weight {
+                return rarity;
+            }
+            roll -= weight;
+        }
+        Rarity::Common
+    }
+
+    /// Rolls an item with the given base id, or a random base item if `None`.
+    ///
+    /// Returns `None` if the item assets are not loaded or `base` is unknown.
+    pub fn roll(&mut self, base: Option<&str>) -> Option<Item> {
+        // Check that an item can be rolled before drawing from the RNG, so a
+        // failed roll does not shift the deterministic sequence.
+        let library = self.item_libraries.get(&self.items.0)?;
+        let rollable = match base {
+            Some(base) => library.items.contains_key(base),
+            None => !library.items.is_empty(),
+        };
+        if !rollable || !self.affix_libraries.contains(&self.affixes.0) {
+            return None;
+        }
+
+        let rarity = self.rarity();
+
+        let items = self.item_libraries.get(&self.items.0)?;
+        let affixes = self.affix_libraries.get(&self.affixes.0)?;
+        let rng: &mut WyRand = &mut self.rng;
+
+        let (id, template) = match base {
+            Some(base) => items.items.get_key_value(base)?,
+            None => items.items.iter().choose(rng)?,
+        };
+        let rarity = rarity.max(template.rarity);
+
+        let kinds = match rarity.affixes() {
+            0 => vec![],
+            1 => vec![if rng.random_bool(0.5) {
+                AffixKind::Prefix
+            } else {
+                AffixKind::Suffix
+            }],
+            _ => vec![AffixKind::Prefix, AffixKind::Suffix],
+        };
+
+        let rolled = kinds
+            .into_iter()
+            .filter_map(|kind| {
+                affixes
+                    .affixes
+                    .iter()
+                    .filter(|(_, affix)| affix.kind == kind && affix.fits(template.slot))
+                    .choose(rng)
+                    .map(|(id, _)| id.clone())
+            })
+            .collect();
+
+        Some(Item {
+            base: id.clone(),
+            rarity,
+            affixes: rolled,
+        })
+    }
+}
@@ -1,29 +1,161 @@
-use crate::tile::{Floor, TilePosition, Wall};
+use crate::{
+    GameState,
+    equipment::Dropped,
+    player::Player,
+    tile::{Floor, PositionQuery, Stairs, TilePosition, Wall},
+    view::MapBounds,
+};
+use bevy::platform::collections::HashSet;
 pub use bevy::prelude::*;
+use std::collections::VecDeque;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, spawn_perlin_level);
+    app.init_resource::<Depth>()
+        .add_systems(Startup, spawn_perlin_level)
+        .add_systems(Update, descend.run_if(in_state(GameState::Overworld)));
 }
 
-fn spawn_perlin_level(mut commands: Commands) {
+/// How deep the player is in the dungeon, starting at 1.
+#[derive(Resource, Deref, DerefMut, Clone, Copy)]
+pub struct Depth(pub u32);
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Triggered after the player took the [`Stairs`] and the next level was
+/// generated around them.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Descended {
+    pub depth: u32,
+}
+
+/// Half the width and height of a level in tiles.
+const LEVEL_SIZE: IVec2 = IVec2::new(100, 100);
+/// How far the stairs may be from where the player enters a level, in steps.
+const STAIRS_DISTANCE: u32 = 40;
+
+fn spawn_perlin_level(mut commands: Commands, depth: Res<Depth>) {
+    spawn_level(&mut commands, depth.0, IVec2::ZERO);
+}
+
+/// Spawns the terrain of level `depth`, with the player entering at
+/// `arrival`, which is always floor. The stairs down are placed on the
+/// reachable floor tile furthest from `arrival`, up to [`STAIRS_DISTANCE`]
+/// steps away. If `arrival` is walled in, a corridor is dug out of it first.
+fn spawn_level(commands: &mut Commands, depth: u32, arrival: IVec2) {
     let perlin_scale = 1.0 / 20.0;
-    let width = 100;
-    let height = 100;
-    for y in -height..height {
-        for x in -width..width {
-            let mut entity =
-                if perlin(Vec2::new(x as f32, y as f32) * perlin_scale + Vec2::new(0.2, 0.2)) * 0.5
-                    + 0.5
-                    > 0.5
-                {
-                    commands.spawn(Wall)
-                } else {
-                    commands.spawn(Floor)
-                };
+    // Every level samples a different part of the noise. The first one keeps
+    // the original offset.
+    let offset = Vec2::new(0.2, 0.2) + Vec2::X * 31.7 * depth.saturating_sub(1) as f32;
+    let bounds = IRect::new(
+        -LEVEL_SIZE.x,
+        -LEVEL_SIZE.y,
+        LEVEL_SIZE.x - 1,
+        LEVEL_SIZE.y - 1,
+    );
+    let noise_wall = |cell: IVec2| {
+        cell != arrival && perlin(cell.as_vec2() * perlin_scale + offset) * 0.5 + 0.5 > 0.5
+    };
+    let corridor = if furthest_floor(arrival, bounds, STAIRS_DISTANCE, noise_wall).is_none() {
+        dig_corridor(arrival, bounds, noise_wall)
+    } else {
+        HashSet::new()
+    };
+    let is_wall = |cell: IVec2| !corridor.contains(&cell) && noise_wall(cell);
+
+    for y in bounds.min.y..=bounds.max.y {
+        for x in bounds.min.x..=bounds.max.x {
+            // Spawn the position with the tile, so its sprite starts out in
+            // the world layer rather than the UI layer.
+            let position = TilePosition(IVec2::new(x, y));
+            if is_wall(position.0) {
+                commands.spawn((Wall, position));
+            } else {
+                commands.spawn((Floor, position));
+            }
+        }
+    }
+
+    if let Some(stairs) = furthest_floor(arrival, bounds, STAIRS_DISTANCE, is_wall) {
+        commands.spawn((Stairs, TilePosition(stairs)));
+    }
+    commands.insert_resource(MapBounds(bounds));
+}
 
-            entity.insert(TilePosition(IVec2::new(x, y)));
+/// The cells of a straight corridor from `from` towards the middle of the
+/// level. It ends where it opens into floor, or after [`STAIRS_DISTANCE`]
+/// cells, so the end is always reachable.
+fn dig_corridor(from: IVec2, bounds: IRect, is_wall: impl Fn(IVec2) -> bool) -> HashSet<IVec2> {
+    let step = if from.x > bounds.center().x {
+        IVec2::NEG_X
+    } else {
+        IVec2::X
+    };
+    let mut corridor = HashSet::new();
+    let mut cell = from;
+    while corridor.len() < STAIRS_DISTANCE as usize && bounds.contains(cell + step) {
+        cell += step;
+        if !is_wall(cell) {
+            break;
         }
+        corridor.insert(cell);
+    }
+    corridor
+}
+
+/// The floor tile furthest from `from` that can be walked to in at most
+/// `max_distance` orthogonal steps, if any besides `from`.
+pub(crate) fn furthest_floor(
+    from: IVec2,
+    bounds: IRect,
+    max_distance: u32,
+    is_wall: impl Fn(IVec2) -> bool,
+) -> Option<IVec2> {
+    const STEPS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+    let mut seen = HashSet::from([from]);
+    let mut frontier = VecDeque::from([(from, 0)]);
+    let mut furthest = None;
+
+    while let Some((cell, distance)) = frontier.pop_front() {
+        if distance > 0 {
+            furthest = Some(cell);
+        }
+        if distance == max_distance {
+            continue;
+        }
+        for step in STEPS {
+            let next = cell + step;
+            if bounds.contains(next) && !is_wall(next) && seen.insert(next) {
+                frontier.push_back((next, distance + 1));
+            }
+        }
+    }
+
+    furthest
+}
+
+fn descend(
+    mut commands: Commands,
+    mut depth: ResMut<Depth>,
+    player: Single<&TilePosition, (With<Player>, Changed<TilePosition>)>,
+    stairs: PositionQuery<(), With<Stairs>>,
+    level: Query<Entity, Or<(With<Wall>, With<Floor>, With<Stairs>, With<Dropped>)>>,
+) {
+    if stairs.iter(*player).next().is_none() {
+        return;
+    }
+
+    for entity in level.iter() {
+        commands.entity(entity).despawn();
     }
+    depth.0 += 1;
+    spawn_level(&mut commands, depth.0, player.0);
+    commands.trigger(Descended { depth: depth.0 });
+    info!("descended to depth {}", depth.0);
 }
 
 fn perlin(st: Vec2) -> f32 {
@@ -0,0 +1,121 @@
+//! Overlays drawn above the world, such as the equipment and arena screens.
+//!
+//! Panels are stacked in the order they are opened. Only the topmost panel
+//! has focus, and while any [`Modal`] panel is open the [`Player`] input
+//! context is suspended so movement keys do not leak into the overworld.
+
+use crate::player::Player;
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use bevy_query_observer::{AddStopObserver, Stop};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<PanelStack>()
+        .add_stop_observer(close_panel)
+        .add_systems(PostUpdate, capture_input);
+}
+
+/// Z of the lowest panel. Every panel gets [`PANEL_DEPTH`] of z to draw in.
+pub const BASE_Z: f32 = 10.0;
+const PANEL_DEPTH: f32 = 10.0;
+
+/// Open panels with the z they draw at, from the bottom to the top.
+#[derive(Resource, Default, Debug)]
+pub struct PanelStack(Vec<(Entity, f32)>);
+
+impl PanelStack {
+    /// Spawns a panel on top of the stack, focusing it.
+    pub fn open(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        let z = self.0.last().map_or(BASE_Z, |(_, z)| z + PANEL_DEPTH);
+        let panel = commands.spawn((Panel, bundle)).id();
+        self.0.push((panel, z));
+        panel
+    }
+
+    /// Despawns `panel` and takes it off the stack right away, so a panel
+    /// opened in its place gets the same z.
+    pub fn close(&mut self, commands: &mut Commands, panel: Entity) {
+        self.0.retain(|(open, _)| *open != panel);
+        commands.entity(panel).despawn();
+    }
+
+    /// The panel that receives input.
+    pub fn focused(&self) -> Option<Entity> {
+        self.0.last().map(|(panel, _)| *panel)
+    }
+
+    pub fn is_focused(&self, panel: Entity) -> bool {
+        self.focused() == Some(panel)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Entity> + '_ {
+        self.0.iter().map(|(panel, _)| *panel)
+    }
+
+    /// The z that tiles `layer` above the bottom of `panel` are drawn at, so
+    /// they cover every panel below it. Layers range from 0 to below
+    /// [`PANEL_DEPTH`].
+    pub fn z(&self, panel: Entity, layer: f32) -> f32 {
+        let base = self
+            .0
+            .iter()
+            .find(|(open, _)| *open == panel)
+            .map_or(BASE_Z, |(_, z)| *z);
+        base + layer.clamp(0.0, PANEL_DEPTH - 1.0)
+    }
+}
+
+/// An open panel. Spawn with [`PanelStack::open`] and close with
+/// [`PanelStack::close`], which also despawns its [`PanelTiles`].
+#[derive(Component, Debug, Default)]
+pub struct Panel;
+
+/// Suspends the overworld input while the panel is open.
+#[derive(Component, Default)]
+pub struct Modal;
+
+#[derive(Component)]
+#[relationship_target(relationship = PanelTileOf, linked_spawn)]
+pub struct PanelTiles(Vec<Entity>);
+
+#[derive(Component)]
+#[relationship(relationship_target = PanelTiles)]
+pub struct PanelTileOf(pub Entity);
+
+/// Run condition for systems that handle the input of a panel marked with `T`.
+pub fn focused<T: Component>(stack: Res<PanelStack>, panels: Query<(), With<T>>) -> bool {
+    stack.focused().is_some_and(|panel| panels.contains(panel))
+}
+
+/// Run condition for overworld systems that should pause under a [`Modal`].
+pub fn no_modal(stack: Res<PanelStack>, modals: Query<(), With<Modal>>) -> bool {
+    !stack.iter().any(|panel| modals.contains(panel))
+}
+
+/// Takes panels despawned without [`PanelStack::close`] off the stack, for
+/// example along with their owner.
+fn close_panel(panel: Stop<(Entity, &Panel)>, mut stack: ResMut<PanelStack>) {
+    let (panel, _) = panel.into_inner();
+    stack.0.retain(|(open, _)| *open != panel);
+}
+
+fn capture_input(
+    stack: Res<PanelStack>,
+    modals: Query<(), With<Modal>>,
+    players: Query<(Entity, Ref<Player>)>,
+    mut commands: Commands,
+) {
+    let added = players.iter().any(|(_, player)| player.is_added());
+    if !stack.is_changed() && !added {
+        return;
+    }
+
+    let active = no_modal(stack, modals);
+    for (player, _) in players.iter() {
+        commands.entity(player).insert(if active {
+            ContextActivity::<Player>::ACTIVE
+        } else {
+            ContextActivity::<Player>::INACTIVE
+        });
+    }
+}
@@ -0,0 +1,225 @@
+//! A picking backend for the tile grid, with click-to-travel and hover
+//! tooltips.
+//!
+//! Sprites are not picked by their bounds. Instead the pointer is mapped to
+//! the [`TilePosition`] under it, and every tile there is hit, with the
+//! highest [`TileZ`] closest.
+
+use crate::{
+    TILE_SIZE,
+    arena::BattleTarget,
+    enemy::Enemy,
+    equipment::Dropped,
+    layout::Widget,
+    panel::PanelStack,
+    player::Player,
+    sprite::WorldCamera,
+    stats::Health,
+    text::StyledText,
+    tile::{Floor, PositionQuery, Stairs, TextAnchor, TilePosition, TileSprite, TileZ, Wall},
+    travel::TravelTo,
+    view::Viewport,
+};
+use bevy::{
+    color::palettes::tailwind::{GRAY_400, RED_400, YELLOW_400},
+    picking::{
+        PickingSystems,
+        backend::{HitData, PointerHits},
+        hover::HoverMap,
+        pointer::{PointerId, PointerLocation},
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PreUpdate, pick_tiles.in_set(PickingSystems::Backend))
+        .add_systems(Update, update_tooltip)
+        .add_observer(click_tile);
+}
+
+/// Width of the hover tooltip in tiles.
+const TOOLTIP_WIDTH: u32 = 18;
+/// Above every [`Panel`](crate::panel::Panel).
+const TOOLTIP_Z: f32 = 90.0;
+
+#[derive(Component)]
+struct Tooltip;
+
+/// The tile under a pointer, from its position in the window.
+fn pointer_tile(window: &Window, viewport: &Viewport, position: Vec2) -> IVec2 {
+    let screen = (position - window.size() / 2.0) * Vec2::new(1.0, -1.0);
+    viewport.center + (screen / TILE_SIZE as f32).floor().as_ivec2()
+}
+
+fn pick_tiles(
+    pointers: Query<(&PointerId, &PointerLocation)>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera: Single<(Entity, &Camera), With<WorldCamera>>,
+    viewport: Res<Viewport>,
+    tiles: PositionQuery<(Entity, &TileZ), With<TileSprite>>,
+    mut output: MessageWriter<PointerHits>,
+) {
+    let Some(window) = window else {
+        return;
+    };
+    let (camera, camera_settings) = *camera;
+
+    for (pointer, location) in pointers.iter() {
+        let Some(location) = location.location() else {
+            continue;
+        };
+        let tile = pointer_tile(&window, &viewport, location.position);
+        if !viewport.contains(tile) {
+            continue;
+        }
+
+        let picks = tiles
+            .iter(&TilePosition(tile))
+            .map(|(entity, z)| (entity, HitData::new(camera, -z.0 as f32, None, None)))
+            .collect::<Vec<_>>();
+        output.write(PointerHits::new(
+            *pointer,
+            picks,
+            camera_settings.order as f32,
+        ));
+    }
+}
+
+fn click_tile(
+    trigger: On<Pointer<Click>>,
+    positions: Query<&TilePosition>,
+    stack: Res<PanelStack>,
+    mut commands: Commands,
+) {
+    // Clicks on an open panel do not reach the world below it.
+    if trigger.button != PointerButton::Primary || stack.focused().is_some() {
+        return;
+    }
+    if let Ok(position) = positions.get(trigger.entity) {
+        commands.trigger(TravelTo { target: position.0 });
+    }
+}
+
+/// What [`describe`] needs to name a tile.
+pub type DescribeData = (
+    Option<&'static Name>,
+    Option<&'static Health>,
+    Has<Player>,
+    Has<Enemy>,
+    Has<BattleTarget>,
+    Has<Dropped>,
+    Has<Wall>,
+    Has<Floor>,
+    Has<Stairs>,
+);
+
+/// A one line label of an entity, or `None` for entities without one.
+pub fn describe(
+    (name, health, player, enemy, target, dropped, wall, floor, stairs): (
+        Option<&Name>,
+        Option<&Health>,
+        bool,
+        bool,
+        bool,
+        bool,
+        bool,
+        bool,
+        bool,
+    ),
+) -> Option<StyledText<'static>> {
+    let label = if player {
+        "You".to_string()
+    } else if let Some(name) = name {
+        name.to_string()
+    } else if enemy {
+        "Enemy".to_string()
+    } else if stairs {
+        "Stairs down".to_string()
+    } else if wall {
+        "Wall".to_string()
+    } else if floor {
+        "Floor".to_string()
+    } else {
+        return None;
+    };
+
+    let color = if enemy || target {
+        RED_400.into()
+    } else if dropped {
+        YELLOW_400.into()
+    } else if wall || floor || stairs {
+        GRAY_400.into()
+    } else {
+        StyledText::FG
+    };
+    let text = StyledText::new().fg(label, color);
+    Some(match health {
+        Some(health) => text.push(format!(" HP {}", health.0)),
+        None => text,
+    })
+}
+
+/// Redraws the tooltip of the hovered tile whenever what it shows may have
+/// changed, such as the health of an entity there or an entity moving away.
+/// Changes anywhere else on the map leave it alone.
+fn update_tooltip(
+    hover: Res<HoverMap>,
+    positions: Query<&TilePosition>,
+    tiles: PositionQuery<DescribeData>,
+    entities: PositionQuery<Entity>,
+    tooltips: Query<Entity, With<Tooltip>>,
+    viewport: Res<Viewport>,
+    stack: Res<PanelStack>,
+    changed: Query<(), Or<(Changed<TilePosition>, Changed<Health>, Changed<Name>)>>,
+    mut shown: Local<(Option<IVec2>, Vec<Entity>)>,
+    mut commands: Commands,
+) {
+    // Every hit of a pointer is on the same tile. Open panels cover the
+    // world, so nothing is described below them.
+    let hovered = hover
+        .values()
+        .flat_map(|hits| hits.keys())
+        .find_map(|entity| positions.get(*entity).ok())
+        .copied()
+        .filter(|_| stack.focused().is_none());
+    // Entities arriving, leaving or despawning change who is there.
+    let here = hovered
+        .map(|position| entities.iter(&position).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if hovered.map(|p| p.0) == shown.0
+        && here == shown.1
+        && !viewport.is_changed()
+        && !here.iter().any(|entity| changed.contains(*entity))
+    {
+        return;
+    }
+    *shown = (hovered.map(|p| p.0), here);
+
+    for tooltip in tooltips.iter() {
+        commands.entity(tooltip).despawn();
+    }
+    let Some(position) = hovered else {
+        return;
+    };
+
+    let lines = tiles
+        .iter(&position)
+        .filter_map(describe)
+        .map(Widget::text)
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return;
+    }
+
+    // Next to the hovered tile, in screen space.
+    let corner = position.0 - viewport.center + IVec2::new(1, 1);
+    let tooltip = Widget::border(Widget::column(lines)).render(TOOLTIP_WIDTH);
+    for (tile, translation) in tooltip.tiles(corner.x, corner.y, TextAnchor::TopLeft) {
+        commands.spawn((
+            tile,
+            Transform::from_translation(translation.extend(TOOLTIP_Z)),
+            Tooltip,
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 use crate::{
     arena::{Attack, Death},
-    equipment::{EquipmentOf, Health, HealthUnit, ShieldUnit},
+    equipment::{EquipmentOf, Equipped},
     input::Move,
+    item::Item,
+    level::Experience,
     observer::ObserverSystem,
+    stats::{BaseStats, Health},
     tile::{MoveIntent, Solid, TilePosition, TileSprite, TileZ},
 };
 use bevy::{color::palettes::css::WHITE, prelude::*};
@@ -10,7 +13,7 @@ use bevy_enhanced_input::prelude::Fire;
 
 #[derive(Component)]
 #[require(
-    TilePosition, TileSprite::PLAYER, TileZ(1), Solid,
+    TilePosition, TileSprite::PLAYER, TileZ(2), Solid, BaseStats::PLAYER, Experience,
     ObserverSystem::<Attack>::on(Self::observe_hit),
 )]
 pub struct Player;
@@ -35,6 +38,10 @@ impl Player {
     }
 }
 
+impl BaseStats {
+    pub const PLAYER: Self = Self::new(2, 0, 5, 0);
+}
+
 impl TileSprite {
     pub const PLAYER: Self = Self {
         ascii: b'p',
@@ -46,8 +53,8 @@ impl TileSprite {
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, |mut commands: Commands| {
         let player = commands.spawn((Player, TilePosition::new(0, 0))).id();
-        commands.spawn((HealthUnit(10), EquipmentOf(player)));
-        commands.spawn((ShieldUnit(10), EquipmentOf(player)));
+        commands.spawn((Item::new("health_unit"), Equipped, EquipmentOf(player)));
+        commands.spawn((Item::new("shield_unit"), Equipped, EquipmentOf(player)));
     })
     .add_observer(move_player);
 }
@@ -57,6 +64,11 @@ fn move_player(
     player: Single<Entity, With<Player>>,
     mut commands: Commands,
 ) {
+    // Look mode reads the same action to move its cursor.
+    if trigger.context != *player {
+        return;
+    }
+
     let direction = trigger.value;
 
     let x = direction.x.round() as i32;
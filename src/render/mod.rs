@@ -1,18 +1,190 @@
-// let view = surface_texture
-//     .texture
-//     .create_view(&wgpu::TextureViewDescriptor {
-//         format: Some(surface_format.add_srgb_suffix()),
-//         ..Default::default()
-//     });
-//
-// let mut encoder = device.create_command_encoder(&Default::default());
-// world.term_renderer.render(&mut encoder, &view);
-// queue.submit([encoder.finish()]);
+//! Draws the tile grid with instanced glyphs instead of one sprite per tile.
+//!
+//! [`TermRendererPlugin`] captures a [`TileGrid`] of the screen every frame
+//! and draws it with [`TermRenderer`] at the end of the main pass of the
+//! world camera.
 
+pub use crate::tileset::{Tileset, TilesetError};
+
+use crate::{
+    hud, picking,
+    snapshot::TileGrid,
+    sprite::WorldCamera,
+    tileset::{TILESET_PATH, TilesetPlugin},
+    view::{Viewport, follow_player},
+};
+use bevy::{
+    color::ColorToComponents,
+    core_pipeline::core_2d::graph::{Core2d, Node2d},
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        view::{ExtractedView, ViewTarget},
+    },
+};
+use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
-pub fn byte_slice<T>(slice: &[T]) -> &[u8] {
-    unsafe { core::slice::from_raw_parts(slice.as_ptr().cast(), std::mem::size_of_val(slice)) }
+/// Draws the screen with [`TermRenderer`], in place of the sprites of
+/// [`PresentationPlugin`](crate::PresentationPlugin).
+pub struct TermRendererPlugin;
+
+impl Plugin for TermRendererPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TilesetPlugin>() {
+            app.add_plugins(TilesetPlugin);
+        }
+        app.init_resource::<TermFrame>()
+            .add_plugins((
+                picking::plugin,
+                hud::plugin,
+                ExtractResourcePlugin::<TermFrame>::default(),
+                ExtractResourcePlugin::<TermTileset>::default(),
+                ExtractComponentPlugin::<TermCamera>::default(),
+            ))
+            .add_systems(Startup, (spawn_camera, load_tileset))
+            .add_systems(
+                Update,
+                share_tileset.run_if(not(resource_exists::<TermTileset>)),
+            )
+            .add_systems(
+                PostUpdate,
+                capture_frame
+                    .after(follow_player)
+                    .after(TransformSystems::Propagate),
+            );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(Render, prepare_term.in_set(RenderSystems::Prepare))
+            .add_render_graph_node::<ViewNodeRunner<TermNode>>(Core2d, TermLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (Node2d::MainTransparentPass, TermLabel, Node2d::EndMainPass),
+            );
+    }
+}
+
+/// The camera whose view the grid is drawn on.
+#[derive(Component, Clone, ExtractComponent)]
+pub struct TermCamera;
+
+/// The screen captured in the main world, for the render world to upload.
+#[derive(Resource, Default, Clone, ExtractResource)]
+struct TermFrame {
+    grid: Option<TileGrid>,
+    /// Tile coordinates at the center of the screen.
+    camera: Vec2,
+    /// Size of the screen in tiles.
+    size: Vec2,
+}
+
+/// The tileset being loaded by the [`AssetServer`].
+#[derive(Resource)]
+struct TermTilesetHandle(Handle<Tileset>);
+
+/// The loaded tileset, copied to the render world once.
+#[derive(Resource, Clone, ExtractResource)]
+struct TermTileset(Tileset);
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct TermLabel;
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((WorldCamera, TermCamera, Camera2d, Msaa::Off));
+}
+
+fn load_tileset(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(TermTilesetHandle(assets.load(TILESET_PATH)));
+}
+
+fn share_tileset(
+    mut commands: Commands,
+    handle: Res<TermTilesetHandle>,
+    tilesets: Res<Assets<Tileset>>,
+) {
+    if let Some(tileset) = tilesets.get(&handle.0) {
+        commands.insert_resource(TermTileset(tileset.clone()));
+    }
+}
+
+fn capture_frame(world: &mut World) {
+    let viewport = *world.resource::<Viewport>();
+    let grid = TileGrid::capture_screen(world, viewport);
+    let size = viewport.size.as_vec2();
+    world.insert_resource(TermFrame {
+        grid: Some(grid),
+        camera: viewport.rect().min.as_vec2() + size / 2.0,
+        size,
+    });
+}
+
+/// Creates the [`TermRenderer`] for the format of the first view, then
+/// uploads the latest frame, scaled to fit the view. Nothing is drawn until
+/// the tileset has loaded.
+fn prepare_term(
+    mut commands: Commands,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    tileset: Option<Res<TermTileset>>,
+    frame: Res<TermFrame>,
+    views: Query<(&ExtractedView, &ViewTarget), With<TermCamera>>,
+    renderer: Option<ResMut<TermRenderer>>,
+) {
+    let (Some(tileset), Some(grid), Ok((view, target))) = (tileset, &frame.grid, views.single())
+    else {
+        return;
+    };
+
+    let mut created = None;
+    let renderer = match renderer {
+        Some(renderer) => renderer.into_inner(),
+        None => created.insert(TermRenderer::new(
+            device.wgpu_device(),
+            &queue,
+            target.main_texture_format(),
+            &tileset.0,
+        )),
+    };
+
+    let viewport = view.viewport.zw().as_vec2();
+    let glyph = Vec2::from_array(renderer.view().tile_size);
+    let zoom = (viewport / (frame.size * glyph)).min_element();
+    renderer.set_view(&queue, viewport.to_array(), frame.camera.to_array(), zoom);
+    renderer.prepare(device.wgpu_device(), &queue, grid);
+
+    if let Some(renderer) = created {
+        commands.insert_resource(renderer);
+    }
+}
+
+#[derive(Default)]
+struct TermNode;
+
+impl ViewNode for TermNode {
+    type ViewQuery = (&'static ViewTarget, &'static TermCamera);
+
+    fn run<'w>(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        (target, _): QueryItem<'w, '_, Self::ViewQuery>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        if let Some(renderer) = world.get_resource::<TermRenderer>() {
+            renderer.render(render_context.command_encoder(), target.main_texture_view());
+        }
+        Ok(())
+    }
 }
 
 pub fn create_render_pipeline(
@@ -106,7 +278,7 @@ pub fn create_render_pipeline(
 }
 
 #[repr(C)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Pod, Zeroable)]
 struct GlyphVertex {
     position: [f32; 3],
     uv: [f32; 2],
@@ -126,16 +298,23 @@ impl GlyphVertex {
     }
 }
 
+/// A single glyph cell, in tile coordinates.
 #[repr(C)]
-#[derive(Copy, Clone)]
-struct Instance {
-    position: [f32; 2],
-    atlas_index: [f32; 2],
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct Instance {
+    pub position: [f32; 2],
+    pub atlas_index: [f32; 2],
+    pub fg: [f32; 4],
+    pub bg: [f32; 4],
 }
 
 impl Instance {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![2 => Float32x2, 3 => Float32x2];
+    const ATTRIBS: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x4,
+        5 => Float32x4
+    ];
 
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
@@ -145,6 +324,33 @@ impl Instance {
             attributes: &Self::ATTRIBS,
         }
     }
+
+    /// One instance for every non-empty cell of the grid, for an atlas with
+    /// `columns` glyphs per row.
+    pub fn from_grid(grid: &TileGrid, columns: u32) -> Vec<Self> {
+        let columns = columns.max(1);
+        let top = grid.origin.y + grid.size.y as i32 - 1;
+        grid.rows()
+            .enumerate()
+            .flat_map(|(row, cells)| {
+                cells.iter().enumerate().filter_map(move |(column, cell)| {
+                    let tile = (*cell)?;
+                    Some(Self {
+                        position: [
+                            (grid.origin.x + column as i32) as f32,
+                            (top - row as i32) as f32,
+                        ],
+                        atlas_index: [
+                            (tile.ascii as u32 % columns) as f32,
+                            (tile.ascii as u32 / columns) as f32,
+                        ],
+                        fg: tile.fg.to_linear().to_f32_array(),
+                        bg: tile.bg.to_linear().to_f32_array(),
+                    })
+                })
+            })
+            .collect()
+    }
 }
 
 const VERTICES: &[GlyphVertex] = &[
@@ -174,22 +380,38 @@ const VERTICES: &[GlyphVertex] = &[
     },
 ];
 
-const INSTANCES: &[Instance] = &[
-    Instance {
-        position: [0.0, 0.0],
-        atlas_index: [4.0, 4.0],
-    },
-    Instance {
-        position: [1.0, 1.0],
-        atlas_index: [6.0, 6.0],
-    },
-];
+/// Instances the buffer is first created with, to avoid regrowing it while
+/// the first few panels open.
+const INITIAL_INSTANCES: usize = 64 * 64;
 
+/// Where and how large the grid is drawn.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct ViewUniform {
+    /// Size of the render target in pixels.
+    pub viewport: [f32; 2],
+    /// Tile coordinates drawn at the center of the viewport.
+    pub camera: [f32; 2],
+    /// Columns and rows of glyphs in the atlas.
+    pub atlas_grid: [f32; 2],
+    /// Size of a tile in pixels at a zoom of 1.
+    pub tile_size: [f32; 2],
+    pub zoom: f32,
+    pub _padding: f32,
+}
+
+#[derive(Resource)]
 pub struct TermRenderer {
     pipeline: wgpu::RenderPipeline,
     vertices: wgpu::Buffer,
     instances: wgpu::Buffer,
+    /// Instances the buffer can hold before it has to be recreated.
+    capacity: usize,
+    len: u32,
     atlas_bind_group: wgpu::BindGroup,
+    view: ViewUniform,
+    view_buffer: wgpu::Buffer,
+    view_bind_group: wgpu::BindGroup,
 }
 
 impl TermRenderer {
@@ -197,9 +419,17 @@ impl TermRenderer {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         surface_format: wgpu::TextureFormat,
-        tileset_ppm: &[u8],
+        tileset: &Tileset,
     ) -> Self {
-        let (atlas, sampler) = parse_ppm(device, queue, tileset_ppm);
+        let (atlas, sampler) = create_atlas(device, queue, tileset);
+        let view = ViewUniform {
+            viewport: [crate::WIDTH as f32, crate::HEIGHT as f32],
+            camera: [0.0, 0.0],
+            atlas_grid: tileset.grid.map(|n| n as f32),
+            tile_size: tileset.glyph_size.map(|n| n as f32),
+            zoom: 1.0,
+            _padding: 0.0,
+        };
 
         let atlas_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -238,20 +468,44 @@ impl TermRenderer {
             ],
         });
 
+        let view_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("view bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("term view buffer"),
+            contents: bytemuck::bytes_of(&view),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("view bind group"),
+            layout: &view_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("term vertex buffer"),
-            contents: byte_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let instances = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("term instance buffer"),
-            contents: byte_slice(INSTANCES),
+            contents: bytemuck::cast_slice(VERTICES),
             usage: wgpu::BufferUsages::VERTEX,
         });
+        let instances = create_instance_buffer(device, INITIAL_INSTANCES);
 
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("term pipeline layout"),
-            bind_group_layouts: &[&atlas_bind_group_layout],
+            bind_group_layouts: &[&atlas_bind_group_layout, &view_bind_group_layout],
             push_constant_ranges: &[],
         });
         let shader = wgpu::include_wgsl!("shaders/term.wgsl");
@@ -268,10 +522,51 @@ impl TermRenderer {
             pipeline,
             vertices,
             instances,
+            capacity: INITIAL_INSTANCES,
+            len: 0,
             atlas_bind_group,
+            view,
+            view_buffer,
+            view_bind_group,
         }
     }
 
+    pub fn view(&self) -> ViewUniform {
+        self.view
+    }
+
+    /// Updates the viewport, camera and zoom, for example after the window
+    /// is resized.
+    pub fn set_view(
+        &mut self,
+        queue: &wgpu::Queue,
+        viewport: [f32; 2],
+        camera: [f32; 2],
+        zoom: f32,
+    ) {
+        let view = ViewUniform {
+            viewport,
+            camera,
+            zoom,
+            ..self.view
+        };
+        if view != self.view {
+            self.view = view;
+            queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&view));
+        }
+    }
+
+    /// Uploads the cells of `grid` to draw on the next [`TermRenderer::render`].
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, grid: &TileGrid) {
+        let instances = Instance::from_grid(grid, self.view.atlas_grid[0] as u32);
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.instances = create_instance_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+        self.len = instances.len() as u32;
+    }
+
     pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("term render pass"),
@@ -290,53 +585,31 @@ impl TermRenderer {
 
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.atlas_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.view_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertices.slice(..));
         render_pass.set_vertex_buffer(1, self.instances.slice(..));
-        render_pass.draw(0..6, 0..INSTANCES.len() as u32);
+        render_pass.draw(0..6, 0..self.len);
     }
 }
 
-fn parse_ppm(
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("term instance buffer"),
+        size: (capacity * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_atlas(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    mut tileset_ppm: &[u8],
+    tileset: &Tileset,
 ) -> (wgpu::TextureView, wgpu::Sampler) {
-    fn parse_usize(bytes: &[u8]) -> usize {
-        let mut accum = 0;
-        for byte in bytes.iter() {
-            accum *= 10;
-            accum += (*byte - b'0') as usize;
-        }
-        accum
-    }
-
-    debug_assert_eq!(
-        &tileset_ppm[..3],
-        b"P6\n",
-        "some ppms have a P3 header but that format isn't supported here"
-    );
-    tileset_ppm = &tileset_ppm[3..];
-
-    let mut ppm = tileset_ppm.split(u8::is_ascii_whitespace);
-    let width = parse_usize(ppm.next().unwrap());
-    let height = parse_usize(ppm.next().unwrap());
-    debug_assert_eq!(width, height);
-
-    let maxval = parse_usize(ppm.next().unwrap());
-    assert!(maxval < 256);
-
-    let rgba = ppm
-        .next()
-        .unwrap()
-        .chunks(3)
-        .flat_map(|chunk| [chunk[0], chunk[1], chunk[2], 255])
-        .collect::<Vec<u8>>();
-    debug_assert!(ppm.next().is_none());
-    debug_assert!(rgba.len().is_multiple_of(4));
-
+    let (width, height) = (tileset.width, tileset.height);
     let texture_size = wgpu::Extent3d {
-        width: width as u32,
-        height: height as u32,
+        width,
+        height,
         depth_or_array_layers: 1,
     };
     let texture_atlas = device.create_texture(&wgpu::TextureDescriptor {
@@ -357,11 +630,11 @@ fn parse_ppm(
             origin: wgpu::Origin3d::ZERO,
             aspect: wgpu::TextureAspect::All,
         },
-        &rgba,
+        &tileset.rgba,
         wgpu::TexelCopyBufferLayout {
             offset: 0,
-            bytes_per_row: Some(4 * width as u32),
-            rows_per_image: Some(height as u32),
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
         },
         texture_size,
     );
@@ -3,22 +3,42 @@ struct VertexInput {
     @location(1) uv: vec2<f32>,
     @location(2) position_offset: vec2<f32>,
     @location(3) atlas_uv: vec2<f32>,
+    @location(4) fg: vec4<f32>,
+    @location(5) bg: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
+    @location(1) fg: vec4<f32>,
+    @location(2) bg: vec4<f32>,
 }
 
+struct View {
+    viewport: vec2<f32>,
+    camera: vec2<f32>,
+    atlas_grid: vec2<f32>,
+    tile_size: vec2<f32>,
+    zoom: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> view: View;
+
 @vertex
 fn vs_main(
     in: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.uv = (in.atlas_uv + in.uv) * (16.0 / 256.0);
-	let scale = vec3(1440.0 / 2560.0, 1.0, 1.0);
-	let atlas_scale = vec3(16.0 / 256.0, 16.0 / 256.0, 1.0);
-    out.clip_position = vec4<f32>((in.position + vec3(in.position_offset, 1.0)) * scale * atlas_scale, 1.0);
+    out.uv = (in.atlas_uv + in.uv) / view.atlas_grid;
+    out.fg = in.fg;
+    out.bg = in.bg;
+
+    // Quad vertices are centered on zero, while tiles are positioned by their
+    // bottom left corner.
+    let tile = in.position.xy + 0.5 + in.position_offset - view.camera;
+    let pixels = tile * view.tile_size * view.zoom;
+    out.clip_position = vec4<f32>(pixels / (view.viewport * 0.5), 0.0, 1.0);
     return out;
 }
 
@@ -29,5 +49,9 @@ var atlas_sampler: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(atlas, atlas_sampler, in.uv);
+    // Glyphs are white on black or transparent, so either channel masks
+    // between the background and foreground colors.
+    let texel = textureSample(atlas, atlas_sampler, in.uv);
+    let coverage = texel.a * max(texel.r, max(texel.g, texel.b));
+    return mix(in.bg, in.fg, coverage);
 }
@@ -0,0 +1,418 @@
+use crate::{
+    DataDir, GameState,
+    arena::{BattleState, BattleTarget, Blocking},
+    controls::{Control, Controls},
+    enemy::{Droid, LastWalk, WalkTimer},
+    equipment::{Dropped, Equipment, EquipmentOf, Equipped},
+    item::{AffixLibrary, Affixes, Item, ItemLibrary, Items, resolve_item},
+    level::Experience,
+    loot::LootRoll,
+    mapgen::Depth,
+    panel,
+    player::Player,
+    stats::{Health, Modifier, Modifiers, StatusEffectOf, StatusEffects},
+    tile::{Floor, Stairs, TilePosition, Wall},
+    view::MapBounds,
+};
+use bevy::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::Duration};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, save_load_input.run_if(panel::no_modal));
+}
+
+/// Bumped whenever [`SaveFile`] changes in a way that breaks older saves.
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_PATH: &str = "save.ron";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub state: GameState,
+    pub battle: Option<BattleState>,
+    pub depth: u32,
+    pub rng: WyRand,
+    pub terrain: SavedTerrain,
+    pub actors: Vec<SavedActor>,
+    pub ground: Vec<((i32, i32), SavedItem)>,
+}
+
+/// Every [`Wall`] and [`Floor`] tile, stored as rows of `x` and `.` from the
+/// bottom left `origin`. Floor with [`Stairs`] is `>`, and cells without
+/// terrain are spaces.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedTerrain {
+    pub origin: (i32, i32),
+    pub rows: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActorKind {
+    Player,
+    Droid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedActor {
+    pub kind: ActorKind,
+    pub position: (i32, i32),
+    pub health: i32,
+    pub modifiers: Vec<Modifier>,
+    pub experience: Option<Experience>,
+    pub battle_target: Option<usize>,
+    pub blocking: bool,
+    /// Elapsed time of the walk timer and the last walk direction.
+    pub walk: Option<(f32, (i32, i32))>,
+    pub equipment: Vec<SavedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SavedItem {
+    Item { item: Item, equipped: bool },
+    Roll { base: Option<String> },
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    Version(u32),
+    /// Entities with a [`TilePosition`] that saves do not store, so loading
+    /// the save would lose them. Each is described by its [`Name`] or id.
+    Unsaved(Vec<String>),
+}
+
+impl core::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Serialize(e) => e.fmt(f),
+            Self::Deserialize(e) => e.fmt(f),
+            Self::Version(version) => {
+                write!(
+                    f,
+                    "save version {version} is not supported, expected {SAVE_VERSION}"
+                )
+            }
+            Self::Unsaved(entities) => write!(
+                f,
+                "saves do not store these entities on the map: {}",
+                entities.join(", ")
+            ),
+        }
+    }
+}
+
+impl core::error::Error for SaveError {}
+
+fn save_load_input(
+    mut commands: Commands,
+    controls: Res<Controls>,
+    data_dir: Res<DataDir>,
+    input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let path = data_dir.0.join(SAVE_PATH);
+    if controls.just_pressed(Control::Save, &input, &gamepads) {
+        commands.queue(move |world: &mut World| match save_to_file(world, &path) {
+            Ok(()) => info!("saved game to {}", path.display()),
+            Err(e) => error!("failed to save game: {e}"),
+        });
+    } else if controls.just_pressed(Control::Load, &input, &gamepads) {
+        commands.queue(
+            move |world: &mut World| match load_from_file(world, &path) {
+                Ok(()) => info!("loaded game from {}", path.display()),
+                Err(e) => error!("failed to load game: {e}"),
+            },
+        );
+    }
+}
+
+pub fn save_to_file(world: &mut World, path: impl AsRef<Path>) -> Result<(), SaveError> {
+    let save = save(world)?;
+    let ron = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(SaveError::Serialize)?;
+    std::fs::write(path, ron).map_err(SaveError::Io)
+}
+
+pub fn load_from_file(world: &mut World, path: impl AsRef<Path>) -> Result<(), SaveError> {
+    let ron = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+    let save = ron::from_str::<SaveFile>(&ron).map_err(SaveError::Deserialize)?;
+    load(world, save)
+}
+
+fn position(position: &TilePosition) -> (i32, i32) {
+    (position.x, position.y)
+}
+
+fn save_item(world: &World, entity: Entity) -> Option<SavedItem> {
+    let entity = world.entity(entity);
+    if let Some(item) = entity.get::<Item>() {
+        Some(SavedItem::Item {
+            item: item.clone(),
+            equipped: entity.contains::<Equipped>(),
+        })
+    } else {
+        entity.get::<LootRoll>().map(|roll| SavedItem::Roll {
+            base: roll.base.clone(),
+        })
+    }
+}
+
+/// Captures the current game into a [`SaveFile`].
+///
+/// Fails with [`SaveError::Unsaved`] if an entity on the map is none of the
+/// terrain, actors and dropped items that saves store, rather than silently
+/// leaving it out.
+pub fn save(world: &mut World) -> Result<SaveFile, SaveError> {
+    let mut unsaved = world.query_filtered::<(Entity, Option<&Name>), (
+        With<TilePosition>,
+        Without<Wall>,
+        Without<Floor>,
+        Without<Stairs>,
+        Without<Player>,
+        Without<Droid>,
+        Without<Dropped>,
+    )>();
+    let unsaved = unsaved
+        .iter(world)
+        .map(|(entity, name)| name.map_or_else(|| entity.to_string(), |name| name.to_string()))
+        .collect::<Vec<_>>();
+    if !unsaved.is_empty() {
+        return Err(SaveError::Unsaved(unsaved));
+    }
+
+    let mut terrain =
+        world.query_filtered::<(&TilePosition, Has<Wall>), Or<(With<Wall>, With<Floor>)>>();
+    let tiles = terrain
+        .iter(world)
+        .map(|(position, wall)| (position.0, wall))
+        .collect::<Vec<_>>();
+    let mut stairs = world.query_filtered::<&TilePosition, With<Stairs>>();
+    let stairs = stairs.iter(world).map(|p| p.0).collect::<Vec<_>>();
+    let terrain = match (
+        tiles.iter().map(|(p, _)| *p).reduce(IVec2::min),
+        tiles.iter().map(|(p, _)| *p).reduce(IVec2::max),
+    ) {
+        (Some(min), Some(max)) => {
+            let size = max - min + IVec2::ONE;
+            let mut rows = vec![vec![b' '; size.x as usize]; size.y as usize];
+            for (position, wall) in tiles {
+                let cell = position - min;
+                rows[cell.y as usize][cell.x as usize] = if wall { b'x' } else { b'.' };
+            }
+            for position in stairs {
+                let cell = position - min;
+                rows[cell.y as usize][cell.x as usize] = b'>';
+            }
+            SavedTerrain {
+                origin: (min.x, min.y),
+                rows: rows
+                    .into_iter()
+                    .map(|row| String::from_utf8(row).unwrap())
+                    .collect(),
+            }
+        }
+        _ => SavedTerrain::default(),
+    };
+
+    let mut actors = world.query_filtered::<(
+        Has<Player>,
+        &TilePosition,
+        &Health,
+        Option<&Modifiers>,
+        Option<&Experience>,
+        Option<&BattleTarget>,
+        Option<(&WalkTimer, &LastWalk)>,
+        Option<&Equipment>,
+        Option<&StatusEffects>,
+    ), Or<(With<Player>, With<Droid>)>>();
+    let actors = actors
+        .iter(world)
+        .map(
+            |(player, pos, health, modifiers, experience, target, walk, equipment, effects)| {
+                SavedActor {
+                    kind: if player {
+                        ActorKind::Player
+                    } else {
+                        ActorKind::Droid
+                    },
+                    position: position(pos),
+                    health: health.0,
+                    modifiers: modifiers.map(|m| m.0.clone()).unwrap_or_default(),
+                    experience: experience.copied(),
+                    battle_target: target.map(|t| t.0),
+                    blocking: effects.is_some_and(|effects| {
+                        effects
+                            .iter()
+                            .any(|e| world.entity(e).contains::<Blocking>())
+                    }),
+                    walk: walk
+                        .map(|(timer, last)| (timer.timer.elapsed_secs(), (last.0.x, last.0.y))),
+                    equipment: equipment
+                        .into_iter()
+                        .flat_map(|e| e.iter())
+                        .filter_map(|e| save_item(world, e))
+                        .collect(),
+                }
+            },
+        )
+        .collect();
+
+    let mut ground = world.query_filtered::<(Entity, &TilePosition), With<Dropped>>();
+    let ground = ground
+        .iter(world)
+        .filter_map(|(entity, pos)| Some((position(pos), save_item(world, entity)?)))
+        .collect();
+
+    let mut rng = world.query_filtered::<&WyRand, With<GlobalRng>>();
+    let rng = rng.single(world).expect("global rng").clone();
+
+    Ok(SaveFile {
+        version: SAVE_VERSION,
+        state: *world.resource::<State<GameState>>().get(),
+        battle: world
+            .get_resource::<State<BattleState>>()
+            .map(|state| state.get().clone()),
+        depth: world.resource::<Depth>().0,
+        rng,
+        terrain,
+        actors,
+        ground,
+    })
+}
+
+fn spawn_item(world: &mut World, item: SavedItem) -> EntityWorldMut<'_> {
+    match item {
+        SavedItem::Item { item, equipped } => {
+            // Resolve right away rather than in the next PreUpdate, so stats
+            // computed this frame already count the modifiers of the gear and
+            // do not clamp the restored health.
+            let library = world
+                .get_resource::<Items>()
+                .and_then(|items| world.resource::<Assets<ItemLibrary>>().get(&items.0));
+            let affixes = world
+                .get_resource::<Affixes>()
+                .and_then(|affixes| world.resource::<Assets<AffixLibrary>>().get(&affixes.0));
+            let resolved = library
+                .zip(affixes)
+                .and_then(|(library, affixes)| resolve_item(&item, library, affixes));
+            let mut entity = world.spawn(item);
+            if let Some(resolved) = resolved {
+                entity.insert(resolved);
+            }
+            if equipped {
+                entity.insert(Equipped);
+            }
+            entity
+        }
+        SavedItem::Roll { base } => world.spawn(LootRoll { base }),
+    }
+}
+
+/// Replaces the current game with the contents of a [`SaveFile`].
+pub fn load(world: &mut World, save: SaveFile) -> Result<(), SaveError> {
+    if save.version != SAVE_VERSION {
+        return Err(SaveError::Version(save.version));
+    }
+
+    let mut despawn = world.query_filtered::<Entity, Or<(
+        With<TilePosition>,
+        With<Item>,
+        With<LootRoll>,
+        With<StatusEffectOf>,
+    )>>();
+    for entity in despawn.iter(world).collect::<Vec<_>>() {
+        // Status effects are despawned along with their target.
+        let _ = world.try_despawn(entity);
+    }
+
+    let (ox, oy) = save.terrain.origin;
+    let rows = save.terrain.rows.len() as i32;
+    let columns = save
+        .terrain
+        .rows
+        .iter()
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0) as i32;
+    if rows > 0 && columns > 0 {
+        world.insert_resource(MapBounds(IRect::new(
+            ox,
+            oy,
+            ox + columns - 1,
+            oy + rows - 1,
+        )));
+    }
+    for (y, row) in save.terrain.rows.iter().enumerate() {
+        for (x, cell) in row.bytes().enumerate() {
+            let position = TilePosition::new(ox + x as i32, oy + y as i32);
+            match cell {
+                b'x' => {
+                    world.spawn((Wall, position));
+                }
+                b'.' => {
+                    world.spawn((Floor, position));
+                }
+                b'>' => {
+                    world.spawn((Floor, position));
+                    world.spawn((Stairs, position));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for actor in save.actors {
+        let (x, y) = actor.position;
+        let mut entity = match actor.kind {
+            ActorKind::Player => world.spawn(Player),
+            ActorKind::Droid => world.spawn(Droid),
+        };
+        entity.insert((
+            TilePosition::new(x, y),
+            Health(actor.health),
+            Modifiers(actor.modifiers),
+        ));
+        if let Some(experience) = actor.experience {
+            entity.insert(experience);
+        }
+        if let Some(target) = actor.battle_target {
+            entity.insert(BattleTarget(target));
+        }
+        if let Some((elapsed, (lx, ly))) = actor.walk {
+            entity.insert(LastWalk(IVec2::new(lx, ly)));
+            if let Some(mut timer) = entity.get_mut::<WalkTimer>() {
+                timer.timer.set_elapsed(Duration::from_secs_f32(elapsed));
+            }
+        }
+        let owner = entity.id();
+
+        if actor.blocking {
+            world.spawn((Blocking, StatusEffectOf(owner)));
+        }
+        for item in actor.equipment {
+            spawn_item(world, item).insert(EquipmentOf(owner));
+        }
+    }
+
+    for ((x, y), item) in save.ground {
+        spawn_item(world, item).insert((Dropped, TilePosition::new(x, y)));
+    }
+
+    let mut rng = world.query_filtered::<&mut WyRand, With<GlobalRng>>();
+    if let Ok(mut global) = rng.single_mut(world) {
+        *global = save.rng;
+    }
+    world.resource_mut::<Depth>().0 = save.depth;
+
+    world.resource_mut::<NextState<GameState>>().set(save.state);
+    if let Some(battle) = save.battle {
+        world.resource_mut::<NextState<BattleState>>().set(battle);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! PNG screenshots of the tile grid, drawn on the CPU so they work in every
+//! frontend and in headless apps. [`Control::Screenshot`] writes one to the
+//! [`DataDir`].
+
+use crate::{
+    DataDir,
+    controls::{Control, Controls},
+    snapshot::TileGrid,
+    tileset::{TILESET_PATH, Tileset, TilesetPlugin},
+    view::Viewport,
+};
+use bevy::{color::ColorToPacked, prelude::*};
+use std::{path::Path, time::SystemTime};
+
+pub fn plugin(app: &mut App) {
+    if !app.is_plugin_added::<TilesetPlugin>() {
+        app.add_plugins(TilesetPlugin);
+    }
+    app.add_systems(Startup, load_tileset)
+        .add_systems(Update, screenshot_input);
+}
+
+/// The tileset screenshots are drawn with.
+#[derive(Resource)]
+struct ScreenshotTileset(Handle<Tileset>);
+
+fn load_tileset(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(ScreenshotTileset(assets.load(TILESET_PATH)));
+}
+
+fn screenshot_input(
+    mut commands: Commands,
+    controls: Res<Controls>,
+    data_dir: Res<DataDir>,
+    input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    if !controls.just_pressed(Control::Screenshot, &input, &gamepads) {
+        return;
+    }
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = data_dir.0.join(format!("screenshot-{time}.png"));
+    commands.queue(
+        move |world: &mut World| match save_screenshot(world, &path) {
+            Ok(()) => info!("saved screenshot to {}", path.display()),
+            Err(e) => error!("failed to save screenshot: {e}"),
+        },
+    );
+}
+
+/// Rasterizes what is on screen, including panels and the HUD. Returns
+/// `None` while the tileset is loading.
+pub fn capture(world: &mut World) -> Option<Screenshot> {
+    let viewport = *world.resource::<Viewport>();
+    let grid = TileGrid::capture_screen(world, viewport);
+    let handle = &world.get_resource::<ScreenshotTileset>()?.0;
+    let tileset = world.resource::<Assets<Tileset>>().get(handle)?;
+    Some(Screenshot::rasterize(&grid, tileset))
+}
+
+/// Captures the screen and writes it to `path` as a PNG.
+pub fn save_screenshot(world: &mut World, path: impl AsRef<Path>) -> Result<(), ScreenshotError> {
+    capture(world).ok_or(ScreenshotError::Loading)?.save(path)
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    /// The tileset has not loaded yet.
+    Loading,
+    Io(std::io::Error),
+    Png(png::EncodingError),
+}
+
+impl core::fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Loading => write!(f, "the tileset is still loading"),
+            Self::Io(e) => e.fmt(f),
+            Self::Png(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for ScreenshotError {}
+
+/// An 8-bit sRGB RGBA image of a [`TileGrid`].
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Composites every cell of `grid` on the CPU.
+    ///
+    /// Like the sprites spawned for a [`TileSprite`](crate::tile::TileSprite),
+    /// each glyph is tinted with `fg` and blended in linear space over a
+    /// solid `bg` quad. Empty cells are black.
+    pub fn rasterize(grid: &TileGrid, tileset: &Tileset) -> Self {
+        let [glyph_width, glyph_height] = tileset.glyph_size;
+        let width = grid.size.x * glyph_width;
+        let height = grid.size.y * glyph_height;
+        let mut rgba = vec![0; (width * height * 4) as usize];
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+
+        let columns = tileset.grid[0];
+        let glyphs = columns * tileset.grid[1];
+        for (row, cells) in grid.rows().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let Some(tile) = cell else {
+                    continue;
+                };
+                let glyph = tile.ascii as u32 % glyphs;
+                let atlas_x = glyph % columns * glyph_width;
+                let atlas_y = glyph / columns * glyph_height;
+                let fg = tile.fg.to_linear();
+                let bg = tile.bg.to_linear();
+
+                for y in 0..glyph_height {
+                    for x in 0..glyph_width {
+                        let source = (((atlas_y + y) * tileset.width + atlas_x + x) * 4) as usize;
+                        let [r, g, b, a]: [u8; 4] =
+                            tileset.rgba[source..source + 4].try_into().unwrap();
+                        let texel = LinearRgba::from(Srgba::rgba_u8(r, g, b, a));
+                        let alpha = texel.alpha * fg.alpha;
+                        let blend = |glyph: f32, tint: f32, under: f32| {
+                            glyph * tint * alpha + under * bg.alpha * (1.0 - alpha)
+                        };
+                        let color = LinearRgba::rgb(
+                            blend(texel.red, fg.red, bg.red),
+                            blend(texel.green, fg.green, bg.green),
+                            blend(texel.blue, fg.blue, bg.blue),
+                        );
+
+                        let px = column as u32 * glyph_width + x;
+                        let py = row as u32 * glyph_height + y;
+                        let target = ((py * width + px) * 4) as usize;
+                        rgba[target..target + 3]
+                            .copy_from_slice(&Srgba::from(color).to_u8_array_no_alpha());
+                    }
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    pub fn encode_png(&self, writer: impl std::io::Write) -> Result<(), ScreenshotError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(ScreenshotError::Png)?;
+        writer
+            .write_image_data(&self.rgba)
+            .map_err(ScreenshotError::Png)?;
+        writer.finish().map_err(ScreenshotError::Png)
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), ScreenshotError> {
+        let file = std::fs::File::create(path).map_err(ScreenshotError::Io)?;
+        self.encode_png(std::io::BufWriter::new(file))
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    TILE_SIZE, glyph,
+    tile::{TilePosition, TileSprite, TileZ},
+    view::Viewport,
+};
+use bevy::{color::ColorToPacked, prelude::*};
+use std::fmt::Write;
+
+/// A cell of a [`TileGrid`]. Empty cells are `None`.
+pub type Cell = Option<TileSprite>;
+
+/// The topmost [`TileSprite`] at every [`TilePosition`] in a region of the
+/// world, for golden-file tests and bug reports.
+#[derive(Clone)]
+pub struct TileGrid {
+    /// Tile coordinates of the bottom left cell.
+    pub origin: IVec2,
+    pub size: UVec2,
+    /// Cells in row-major order, starting from the top row.
+    pub cells: Vec<Cell>,
+}
+
+impl TileGrid {
+    /// Captures every positioned [`TileSprite`] in the world.
+    ///
+    /// Returns an empty grid if the world has no positioned tiles.
+    pub fn capture(world: &mut World) -> Self {
+        let mut tiles = world.query_filtered::<&TilePosition, With<TileSprite>>();
+        let (min, max) = tiles
+            .iter(world)
+            .fold(None, |bounds: Option<(IVec2, IVec2)>, position| {
+                Some(match bounds {
+                    Some((min, max)) => (min.min(position.0), max.max(position.0)),
+                    None => (position.0, position.0),
+                })
+            })
+            .unwrap_or((IVec2::ZERO, IVec2::NEG_ONE));
+        Self::capture_region(world, IRect { min, max })
+    }
+
+    /// Captures the positioned [`TileSprite`]s inside `region`, including its
+    /// `max` corner.
+    ///
+    /// When several tiles share a position, the one with the highest
+    /// [`TileZ`] is kept.
+    pub fn capture_region(world: &mut World, region: IRect) -> Self {
+        let size = (region.max - region.min + IVec2::ONE)
+            .max(IVec2::ZERO)
+            .as_uvec2();
+        let mut grid = Self {
+            origin: region.min,
+            size,
+            cells: vec![None; (size.x * size.y) as usize],
+        };
+        let mut depth = vec![i32::MIN; grid.cells.len()];
+
+        let mut tiles = world.query::<(&TileSprite, &TilePosition, &TileZ)>();
+        for (sprite, position, z) in tiles.iter(world) {
+            let Some(index) = grid.index(position.0) else {
+                continue;
+            };
+            if z.0 >= depth[index] {
+                depth[index] = z.0;
+                grid.cells[index] = Some(*sprite);
+            }
+        }
+        grid
+    }
+
+    /// Captures the tiles in the [`Viewport`], then draws the screen space
+    /// [`TileSprite`]s without a [`TilePosition`], such as panels and the
+    /// HUD, over it in the order of their depth.
+    pub fn capture_screen(world: &mut World, viewport: Viewport) -> Self {
+        let mut grid = Self::capture_region(world, viewport.rect());
+
+        let mut ui = world.query_filtered::<(&TileSprite, &Transform), Without<TilePosition>>();
+        let mut ui = ui
+            .iter(world)
+            .map(|(tile, transform)| (*tile, transform.translation))
+            .collect::<Vec<_>>();
+        ui.sort_by(|(_, a), (_, b)| a.z.total_cmp(&b.z));
+        for (tile, translation) in ui {
+            let position = (translation.truncate() / TILE_SIZE as f32)
+                .floor()
+                .as_ivec2();
+            grid.set(viewport.center + position, tile);
+        }
+        grid
+    }
+
+    fn index(&self, position: IVec2) -> Option<usize> {
+        let cell = position - self.origin;
+        if cell.cmplt(IVec2::ZERO).any() || cell.cmpge(self.size.as_ivec2()).any() {
+            return None;
+        }
+        let row = self.size.y as i32 - 1 - cell.y;
+        Some((row * self.size.x as i32 + cell.x) as usize)
+    }
+
+    /// The cell at the given tile coordinates.
+    pub fn get(&self, position: IVec2) -> Cell {
+        self.index(position).and_then(|index| self.cells[index])
+    }
+
+    /// Replaces the cell at the given tile coordinates, if it is in the grid.
+    pub fn set(&mut self, position: IVec2, tile: TileSprite) {
+        if let Some(index) = self.index(position) {
+            self.cells[index] = Some(tile);
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.size.x.max(1) as usize)
+    }
+
+    /// Renders the grid as plain text, one line per row. Empty cells are
+    /// spaces.
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::with_capacity(self.cells.len() + self.size.y as usize);
+        for row in self.rows() {
+            out.extend(
+                row.iter()
+                    .map(|cell| cell.map_or(' ', |tile| glyph::char(tile.ascii))),
+            );
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the grid with 24-bit ANSI foreground and background colors.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            for cell in row {
+                match cell {
+                    Some(tile) => {
+                        let [fr, fg, fb, _] = tile.fg.to_srgba().to_u8_array();
+                        let [br, bg, bb, _] = tile.bg.to_srgba().to_u8_array();
+                        let _ = write!(
+                            out,
+                            "\x1b[38;2;{fr};{fg};{fb}m\x1b[48;2;{br};{bg};{bb}m{}",
+                            glyph::char(tile.ascii)
+                        );
+                    }
+                    None => out.push_str("\x1b[0m "),
+                }
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    TILE_SIZE,
+    tile::{PositionQuery, TilePosition, TileSprite},
+    view::{Viewport, follow_player},
+};
+use bevy::{camera::visibility::RenderLayers, prelude::*, sprite::Anchor};
+use bevy_query_observer::{AddStartObserver, AddStopObserver, Start, Stop};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PreStartup, spawn_atlas)
+        .add_systems(Startup, spawn_cameras)
+        .add_systems(
+            PostUpdate,
+            (
+                move_camera,
+                relayer_tiles.before(cull_tiles),
+                cull_tiles,
+                update_tile_sprites,
+            )
+                .after(follow_player)
+                .before(TransformSystems::Propagate),
+        )
+        .add_start_observer(tile_sprite)
+        .add_stop_observer(remove_tile_sprite);
+}
+
+/// Render layer of screen space tiles, which have no [`TilePosition`] and
+/// stay in place while the world camera moves.
+pub const UI_LAYER: usize = 1;
+
+#[derive(Component)]
+pub struct WorldCamera;
+
+#[derive(Component)]
+pub struct UiCamera;
+
+#[derive(Component)]
+pub struct TileBg;
+
+fn tile_sprite(
+    tiles: Start<(Entity, &TileSprite)>,
+    positions: Query<&TilePosition>,
+    atlas: Res<TileAtlas>,
+    viewport: Res<Viewport>,
+    mut commands: Commands,
+) {
+    let (entity, tile) = tiles.into_inner();
+
+    if let Ok(position) = positions.get(entity) {
+        if viewport.contains(position.0) {
+            insert_sprite(&mut commands, entity, tile, &atlas, RenderLayers::default());
+        }
+    } else {
+        insert_sprite(
+            &mut commands,
+            entity,
+            tile,
+            &atlas,
+            RenderLayers::layer(UI_LAYER),
+        );
+    }
+}
+
+fn insert_sprite(
+    commands: &mut Commands,
+    entity: Entity,
+    tile: &TileSprite,
+    atlas: &TileAtlas,
+    layers: RenderLayers,
+) {
+    commands.entity(entity).insert((
+        Sprite {
+            image: atlas.image.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: atlas.atlas.clone(),
+                index: tile.ascii as usize,
+            }),
+            color: tile.fg,
+            ..Default::default()
+        },
+        Anchor::BOTTOM_LEFT,
+        layers.clone(),
+    ));
+    commands.spawn((
+        TileBg,
+        Transform::from_xyz(0.0, 0.0, -0.01),
+        Sprite::from_color(tile.bg, Vec2::splat(TILE_SIZE as f32)),
+        Anchor::BOTTOM_LEFT,
+        layers,
+        ChildOf(entity),
+    ));
+}
+
+fn remove_tile_sprite(
+    tiles: Stop<(Entity, &TileSprite, Option<&Children>)>,
+    backgrounds: Query<(), With<TileBg>>,
+    mut commands: Commands,
+) {
+    let (entity, _, children) = tiles.into_inner();
+    remove_sprite(&mut commands, entity, children, &backgrounds);
+}
+
+/// Moves the sprites of tiles that gain or lose a [`TilePosition`] after
+/// they were spawned between the world and the UI layer.
+fn relayer_tiles(
+    mut commands: Commands,
+    mut removed: RemovedComponents<TilePosition>,
+    placed: Query<(Entity, &RenderLayers, Option<&Children>), Added<TilePosition>>,
+    unplaced: Query<(&TileSprite, Option<&RenderLayers>, Option<&Children>), Without<TilePosition>>,
+    atlas: Res<TileAtlas>,
+    backgrounds: Query<(), With<TileBg>>,
+) {
+    let ui = RenderLayers::layer(UI_LAYER);
+
+    // `cull_tiles` gives these a world sprite again if they are on screen.
+    for (entity, layers, children) in placed.iter() {
+        if *layers == ui {
+            remove_sprite(&mut commands, entity, children, &backgrounds);
+        }
+    }
+
+    for entity in removed.read() {
+        let Ok((tile, layers, children)) = unplaced.get(entity) else {
+            continue;
+        };
+        if layers == Some(&ui) {
+            continue;
+        }
+        remove_sprite(&mut commands, entity, children, &backgrounds);
+        insert_sprite(&mut commands, entity, tile, &atlas, ui.clone());
+    }
+}
+
+/// Applies changes to a [`TileSprite`] after it was spawned.
+fn update_tile_sprites(
+    mut tiles: Query<(&TileSprite, &mut Sprite, Option<&Children>), Changed<TileSprite>>,
+    mut backgrounds: Query<&mut Sprite, (With<TileBg>, Without<TileSprite>)>,
+) {
+    for (tile, mut sprite, children) in tiles.iter_mut() {
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = tile.ascii as usize;
+        }
+        sprite.color = tile.fg;
+
+        let mut backgrounds = backgrounds.iter_many_mut(children.into_iter().flatten());
+        while let Some(mut background) = backgrounds.fetch_next() {
+            background.color = tile.bg;
+        }
+    }
+}
+
+fn remove_sprite(
+    commands: &mut Commands,
+    entity: Entity,
+    children: Option<&Children>,
+    backgrounds: &Query<(), With<TileBg>>,
+) {
+    commands
+        .entity(entity)
+        .try_remove::<(Sprite, Anchor, RenderLayers)>();
+    for child in children.into_iter().flatten() {
+        if backgrounds.contains(*child) {
+            commands.entity(*child).try_despawn();
+        }
+    }
+}
+
+/// Only positioned tiles inside the [`Viewport`] have sprites.
+fn cull_tiles(
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    mut last: Local<Option<IRect>>,
+    atlas: Res<TileAtlas>,
+    visible: Query<(Entity, &TilePosition, Option<&Children>), (With<TileSprite>, With<Sprite>)>,
+    moved: Query<(Entity, &TilePosition, &TileSprite), (Without<Sprite>, Changed<TilePosition>)>,
+    hidden: PositionQuery<(Entity, &TileSprite), Without<Sprite>>,
+    backgrounds: Query<(), With<TileBg>>,
+) {
+    let rect = viewport.rect();
+
+    for (entity, position, children) in visible.iter() {
+        if !rect.contains(position.0) {
+            remove_sprite(&mut commands, entity, children, &backgrounds);
+        }
+    }
+
+    for (entity, position, tile) in moved.iter() {
+        if rect.contains(position.0) {
+            insert_sprite(&mut commands, entity, tile, &atlas, RenderLayers::default());
+        }
+    }
+
+    if *last != Some(rect) {
+        let previous = last.unwrap_or_default();
+        for y in rect.min.y..=rect.max.y {
+            for x in rect.min.x..=rect.max.x {
+                let position = IVec2::new(x, y);
+                if last.is_some() && previous.contains(position) {
+                    continue;
+                }
+                for (entity, tile) in hidden.iter(&TilePosition(position)) {
+                    if moved.contains(entity) {
+                        continue;
+                    }
+                    insert_sprite(&mut commands, entity, tile, &atlas, RenderLayers::default());
+                }
+            }
+        }
+        *last = Some(rect);
+    }
+}
+
+#[derive(Resource)]
+pub struct TileAtlas {
+    pub image: Handle<Image>,
+    pub atlas: Handle<TextureAtlasLayout>,
+}
+
+fn spawn_atlas(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    commands.insert_resource(TileAtlas {
+        image: server.load("anno16.png"),
+        atlas: atlas_layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::splat(TILE_SIZE as u32),
+            16,
+            16,
+            None,
+            None,
+        )),
+    });
+}
+
+fn spawn_cameras(mut commands: Commands) {
+    commands.spawn((WorldCamera, Camera2d));
+    commands.spawn((
+        UiCamera,
+        Camera2d,
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        RenderLayers::layer(UI_LAYER),
+    ));
+}
+
+fn move_camera(viewport: Res<Viewport>, mut camera: Single<&mut Transform, With<WorldCamera>>) {
+    if viewport.is_changed() {
+        let center = viewport.center.as_vec2() * TILE_SIZE as f32;
+        camera.translation = center.extend(camera.translation.z);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::equipment::{Equipment, Equipped};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PostUpdate, compute_stats);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    Attack,
+    Defense,
+    Speed,
+    MaxHealth,
+}
+
+impl Stat {
+    pub const ALL: [Self; 4] = [Self::Attack, Self::Defense, Self::Speed, Self::MaxHealth];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Attack => "ATK",
+            Self::Defense => "DEF",
+            Self::Speed => "SPD",
+            Self::MaxHealth => "HP",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StatBlock {
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+    pub max_health: i32,
+}
+
+impl StatBlock {
+    pub fn get(&self, stat: Stat) -> i32 {
+        match stat {
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::Speed => self.speed,
+            Stat::MaxHealth => self.max_health,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> &mut i32 {
+        match stat {
+            Stat::Attack => &mut self.attack,
+            Stat::Defense => &mut self.defense,
+            Stat::Speed => &mut self.speed,
+            Stat::MaxHealth => &mut self.max_health,
+        }
+    }
+}
+
+/// The unmodified stats of an entity.
+///
+/// The effective values are derived into [`Stats`] every frame.
+#[derive(Component, Deref, DerefMut, Clone, Copy)]
+#[require(Stats, Health)]
+pub struct BaseStats(pub StatBlock);
+
+impl BaseStats {
+    pub const fn new(attack: i32, defense: i32, speed: i32, max_health: i32) -> Self {
+        Self(StatBlock {
+            attack,
+            defense,
+            speed,
+            max_health,
+        })
+    }
+}
+
+/// The effective stats of an entity after applying every [`Modifiers`] from
+/// the entity itself, its [`Equipped`] [`Equipment`], and its [`StatusEffects`].
+#[derive(Component, Deref, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats(StatBlock);
+
+/// The current health of an entity, capped by [`Stats::max_health`].
+#[derive(Component, Clone, Deref, DerefMut, Default)]
+pub struct Health(pub i32);
+
+impl Health {
+    pub fn heal(&mut self, amount: i32, stats: &Stats) {
+        self.0 = (self.0 + amount).min(stats.max_health);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ModifierKind {
+    Add(i32),
+    Mul(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Modifier {
+    pub stat: Stat,
+    pub kind: ModifierKind,
+}
+
+impl Modifier {
+    pub const fn add(stat: Stat, value: i32) -> Self {
+        Self {
+            stat,
+            kind: ModifierKind::Add(value),
+        }
+    }
+
+    pub const fn mul(stat: Stat, value: f32) -> Self {
+        Self {
+            stat,
+            kind: ModifierKind::Mul(value),
+        }
+    }
+}
+
+impl core::fmt::Display for Modifier {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.kind {
+            ModifierKind::Add(value) => write!(f, "{:+} {}", value, self.stat.label()),
+            ModifierKind::Mul(value) => {
+                write!(
+                    f,
+                    "{:+}% {}",
+                    ((value - 1.0) * 100.0).round() as i32,
+                    self.stat.label()
+                )
+            }
+        }
+    }
+}
+
+/// Stat modifiers contributed by an entity.
+///
+/// Modifiers apply to the entity itself, the owner of an equipment entity, or
+/// the target of a status effect entity.
+#[derive(Component, Deref, DerefMut, Default, Clone)]
+pub struct Modifiers(pub Vec<Modifier>);
+
+#[derive(Component)]
+#[relationship_target(relationship = StatusEffectOf, linked_spawn)]
+pub struct StatusEffects(Vec<Entity>);
+
+#[derive(Component)]
+#[relationship(relationship_target = StatusEffects)]
+pub struct StatusEffectOf(pub Entity);
+
+/// Damage dealt by an attack after the defender's mitigation.
+///
+/// Defense scales the attack by `attack / (attack + defense)` instead of
+/// being subtracted from it, so each point of defense still counts against
+/// weak attacks. Every attack deals at least 1 damage.
+pub fn damage(attack: i32, defense: i32) -> i32 {
+    let attack = attack.max(1) as f32;
+    let defense = defense.max(0) as f32;
+    (attack * attack / (attack + defense)).round().max(1.0) as i32
+}
+
+pub(crate) fn compute_stats(
+    mut entities: Query<(
+        &BaseStats,
+        &mut Stats,
+        &mut Health,
+        Option<&Modifiers>,
+        Option<&Equipment>,
+        Option<&StatusEffects>,
+    )>,
+    modifiers: Query<&Modifiers>,
+    equipped: Query<&Modifiers, With<Equipped>>,
+) {
+    for (base, mut stats, mut health, own, equipment, effects) in entities.iter_mut() {
+        let mut add = StatBlock::default();
+        let mut mul = [1.0; Stat::ALL.len()];
+
+        let sources = own
+            .into_iter()
+            .chain(equipped.iter_many(equipment.into_iter().flat_map(|e| e.iter())))
+            .chain(modifiers.iter_many(effects.into_iter().flat_map(|e| e.iter())));
+        for modifier in sources.flat_map(|m| m.iter()) {
+            match modifier.kind {
+                ModifierKind::Add(value) => *add.get_mut(modifier.stat) += value,
+                ModifierKind::Mul(value) => mul[modifier.stat as usize] *= value,
+            }
+        }
+
+        let mut derived = StatBlock::default();
+        for stat in Stat::ALL {
+            *derived.get_mut(stat) =
+                ((base.get(stat) + add.get(stat)) as f32 * mul[stat as usize]).round() as i32;
+        }
+
+        if stats.is_added() {
+            // Fresh entities start at full health, while restored entities
+            // keep the health they were saved with.
+            if health.0 == 0 {
+                health.0 = derived.max_health;
+            }
+        } else {
+            // Raising the maximum also raises the current health, so that
+            // newly equipped gear is immediately useful.
+            let gained = derived.max_health - stats.max_health;
+            if gained > 0 {
+                health.0 += gained;
+            }
+        }
+        if health.0 > derived.max_health {
+            health.0 = derived.max_health;
+        }
+
+        stats.set_if_neq(Stats(derived));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{hud, snapshot::TileGrid, view::Viewport};
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput, NativeKey},
+    },
+    prelude::*,
+};
+use crossterm::{
+    cursor, event,
+    event::{Event, KeyEventKind, KeyModifiers},
+    execute, queue, terminal,
+};
+use std::{
+    io::Write,
+    sync::{
+        Mutex,
+        mpsc::{Receiver, channel},
+    },
+};
+
+/// Draws the tile world to the terminal with 24-bit ANSI colors and reads
+/// keyboard input from stdin, in place of a window.
+pub struct TerminalPlugin;
+
+impl Plugin for TerminalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(hud::plugin)
+            .add_systems(PreStartup, setup_terminal)
+            .add_systems(
+                PreUpdate,
+                (
+                    read_terminal_input.before(bevy::input::InputSystems),
+                    fit_viewport,
+                ),
+            )
+            .add_systems(Last, draw_terminal);
+    }
+}
+
+/// Restores the terminal when the app exits.
+#[derive(Resource)]
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Resource)]
+struct TerminalInput {
+    events: Mutex<Receiver<Event>>,
+    /// Keys pressed last frame. Most terminals do not report key releases,
+    /// so every key is released on the frame after it is pressed.
+    held: Vec<KeyCode>,
+}
+
+/// The last frame written to the terminal.
+#[derive(Resource, Default)]
+struct TerminalFrame(String);
+
+fn setup_terminal(mut commands: Commands) -> Result {
+    terminal::enable_raw_mode()?;
+    execute!(
+        std::io::stdout(),
+        terminal::EnterAlternateScreen,
+        cursor::Hide,
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    commands.insert_resource(TerminalGuard);
+
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    commands.insert_resource(TerminalInput {
+        events: Mutex::new(receiver),
+        held: Vec::new(),
+    });
+    commands.init_resource::<TerminalFrame>();
+
+    Ok(())
+}
+
+fn read_terminal_input(
+    mut input: ResMut<TerminalInput>,
+    mut keyboard: MessageWriter<KeyboardInput>,
+    mut exit: MessageWriter<AppExit>,
+    mut frame: ResMut<TerminalFrame>,
+) {
+    let message = |key_code, state| KeyboardInput {
+        key_code,
+        logical_key: Key::Unidentified(NativeKey::Unidentified),
+        state,
+        text: None,
+        repeat: false,
+        window: Entity::PLACEHOLDER,
+    };
+
+    for key_code in core::mem::take(&mut input.held) {
+        keyboard.write(message(key_code, ButtonState::Released));
+    }
+
+    let events = input
+        .events
+        .get_mut()
+        .unwrap()
+        .try_iter()
+        .collect::<Vec<_>>();
+    for event in events {
+        match event {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && key.code == event::KeyCode::Char('c')
+                {
+                    exit.write(AppExit::Success);
+                    continue;
+                }
+                let Some(key_code) = key_code(key.code) else {
+                    warn!("ignoring unmapped terminal key {:?}", key.code);
+                    continue;
+                };
+                keyboard.write(message(key_code, ButtonState::Pressed));
+                input.held.push(key_code);
+            }
+            // Redraw everything after the terminal is resized.
+            Event::Resize(..) => frame.0.clear(),
+            _ => {}
+        }
+    }
+}
+
+fn key_code(code: event::KeyCode) -> Option<KeyCode> {
+    use event::KeyCode as Term;
+
+    Some(match code {
+        Term::Char(c) => match c.to_ascii_lowercase() {
+            'a' => KeyCode::KeyA,
+            'b' => KeyCode::KeyB,
+            'c' => KeyCode::KeyC,
+            'd' => KeyCode::KeyD,
+            'e' => KeyCode::KeyE,
+            'f' => KeyCode::KeyF,
+            'g' => KeyCode::KeyG,
+            'h' => KeyCode::KeyH,
+            'i' => KeyCode::KeyI,
+            'j' => KeyCode::KeyJ,
+            'k' => KeyCode::KeyK,
+            'l' => KeyCode::KeyL,
+            'm' => KeyCode::KeyM,
+            'n' => KeyCode::KeyN,
+            'o' => KeyCode::KeyO,
+            'p' => KeyCode::KeyP,
+            'q' => KeyCode::KeyQ,
+            'r' => KeyCode::KeyR,
+            's' => KeyCode::KeyS,
+            't' => KeyCode::KeyT,
+            'u' => KeyCode::KeyU,
+            'v' => KeyCode::KeyV,
+            'w' => KeyCode::KeyW,
+            'x' => KeyCode::KeyX,
+            'y' => KeyCode::KeyY,
+            'z' => KeyCode::KeyZ,
+            '0' => KeyCode::Digit0,
+            '1' => KeyCode::Digit1,
+            '2' => KeyCode::Digit2,
+            '3' => KeyCode::Digit3,
+            '4' => KeyCode::Digit4,
+            '5' => KeyCode::Digit5,
+            '6' => KeyCode::Digit6,
+            '7' => KeyCode::Digit7,
+            '8' => KeyCode::Digit8,
+            '9' => KeyCode::Digit9,
+            ' ' => KeyCode::Space,
+            // Shifted symbols are read as the key they are on, as laid out
+            // on a US keyboard.
+            '!' => KeyCode::Digit1,
+            '@' => KeyCode::Digit2,
+            '#' => KeyCode::Digit3,
+            '$' => KeyCode::Digit4,
+            '%' => KeyCode::Digit5,
+            '^' => KeyCode::Digit6,
+            '&' => KeyCode::Digit7,
+            '*' => KeyCode::Digit8,
+            '(' => KeyCode::Digit9,
+            ')' => KeyCode::Digit0,
+            '-' | '_' => KeyCode::Minus,
+            '=' | '+' => KeyCode::Equal,
+            '[' | '{' => KeyCode::BracketLeft,
+            ']' | '}' => KeyCode::BracketRight,
+            '\\' | '|' => KeyCode::Backslash,
+            ';' | ':' => KeyCode::Semicolon,
+            '\'' | '"' => KeyCode::Quote,
+            ',' | '<' => KeyCode::Comma,
+            '.' | '>' => KeyCode::Period,
+            '/' | '?' => KeyCode::Slash,
+            '`' | '~' => KeyCode::Backquote,
+            _ => return None,
+        },
+        Term::Up => KeyCode::ArrowUp,
+        Term::Down => KeyCode::ArrowDown,
+        Term::Left => KeyCode::ArrowLeft,
+        Term::Right => KeyCode::ArrowRight,
+        Term::Enter => KeyCode::Enter,
+        Term::Esc => KeyCode::Escape,
+        Term::Tab | Term::BackTab => KeyCode::Tab,
+        Term::Backspace => KeyCode::Backspace,
+        Term::Delete => KeyCode::Delete,
+        Term::Insert => KeyCode::Insert,
+        Term::Home => KeyCode::Home,
+        Term::End => KeyCode::End,
+        Term::PageUp => KeyCode::PageUp,
+        Term::PageDown => KeyCode::PageDown,
+        Term::CapsLock => KeyCode::CapsLock,
+        Term::ScrollLock => KeyCode::ScrollLock,
+        Term::NumLock => KeyCode::NumLock,
+        Term::PrintScreen => KeyCode::PrintScreen,
+        Term::Pause => KeyCode::Pause,
+        Term::Menu => KeyCode::ContextMenu,
+        Term::F(n) => match n {
+            1 => KeyCode::F1,
+            2 => KeyCode::F2,
+            3 => KeyCode::F3,
+            4 => KeyCode::F4,
+            5 => KeyCode::F5,
+            6 => KeyCode::F6,
+            7 => KeyCode::F7,
+            8 => KeyCode::F8,
+            9 => KeyCode::F9,
+            10 => KeyCode::F10,
+            11 => KeyCode::F11,
+            12 => KeyCode::F12,
+            13 => KeyCode::F13,
+            14 => KeyCode::F14,
+            15 => KeyCode::F15,
+            16 => KeyCode::F16,
+            17 => KeyCode::F17,
+            18 => KeyCode::F18,
+            19 => KeyCode::F19,
+            20 => KeyCode::F20,
+            21 => KeyCode::F21,
+            22 => KeyCode::F22,
+            23 => KeyCode::F23,
+            24 => KeyCode::F24,
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// Crops the [`Viewport`] to the terminal size.
+fn fit_viewport(mut viewport: ResMut<Viewport>) {
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let size = Viewport::default()
+        .size
+        .min(UVec2::new(columns as u32, rows as u32));
+    if viewport.size != size {
+        viewport.size = size;
+    }
+}
+
+fn draw_terminal(world: &mut World) {
+    let Some(last) = world.get_resource::<TerminalFrame>() else {
+        return;
+    };
+    let last = last.0.clone();
+
+    let viewport = *world.resource::<Viewport>();
+    let grid = TileGrid::capture_screen(world, viewport);
+
+    let ansi = grid.to_ansi();
+    if ansi == last {
+        return;
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    for (row, line) in ansi.lines().enumerate() {
+        let _ = queue!(stdout, cursor::MoveTo(0, row as u16));
+        let _ = write!(stdout, "{line}");
+    }
+    let _ = stdout.flush();
+    world.resource_mut::<TerminalFrame>().0 = ansi;
+}
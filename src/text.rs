@@ -0,0 +1,219 @@
+use crate::tile::TileSprite;
+use bevy::{
+    color::palettes::tailwind::{
+        BLUE_400, CYAN_400, GRAY_300, GREEN_400, ORANGE_400, PINK_400, PURPLE_400, RED_400,
+        YELLOW_400,
+    },
+    prelude::*,
+};
+use std::borrow::Cow;
+
+/// A run of text drawn with the same colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span<'a> {
+    pub text: Cow<'a, str>,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+/// Text made of differently colored [`Span`]s, for
+/// [`text_tiles`](crate::tile::text_tiles).
+///
+/// Plain strings convert into a single white on black span. Use
+/// [`StyledText::markup`] to color words inline, or build the spans directly:
+///
+/// ```ignore
+/// StyledText::new().push("HP ").fg("12/20", Color::from(RED_400))
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StyledText<'a> {
+    pub spans: Vec<Span<'a>>,
+}
+
+impl<'a> StyledText<'a> {
+    pub const FG: Color = Color::WHITE;
+    pub const BG: Color = Color::BLACK;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends white on black text.
+    pub fn push(self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.span(text, Self::FG, Self::BG)
+    }
+
+    /// Appends text with a foreground color.
+    pub fn fg(self, text: impl Into<Cow<'a, str>>, fg: Color) -> Self {
+        self.span(text, fg, Self::BG)
+    }
+
+    pub fn span(mut self, text: impl Into<Cow<'a, str>>, fg: Color, bg: Color) -> Self {
+        self.spans.push(Span {
+            text: text.into(),
+            fg,
+            bg,
+        });
+        self
+    }
+
+    pub fn append(mut self, other: StyledText<'a>) -> Self {
+        self.spans.extend(other.spans);
+        self
+    }
+
+    /// Parses inline color tags.
+    ///
+    /// `[red]` or `[fg=red]` sets the foreground, `[bg=blue]` the background,
+    /// and `[/]` restores the colors from before the most recent tag. Colors
+    /// are either one of the [named colors](color) or hex like `#ff8000`.
+    /// `[[` draws a literal `[`, and tags that fail to parse are drawn as is.
+    ///
+    /// ```ignore
+    /// StyledText::markup("[red]HP[/] [bg=blue]x10[/]")
+    /// ```
+    pub fn markup(markup: &'a str) -> Self {
+        let mut text = Self::new();
+        let mut stack = vec![(Self::FG, Self::BG)];
+        let mut rest = markup;
+
+        while !rest.is_empty() {
+            let (fg, bg) = *stack.last().unwrap();
+
+            let Some(open) = rest.find('[') else {
+                text = text.span(rest, fg, bg);
+                break;
+            };
+            if open > 0 {
+                text = text.span(&rest[..open], fg, bg);
+            }
+            rest = &rest[open..];
+
+            if let Some(after) = rest.strip_prefix("[[") {
+                text = text.span("[", fg, bg);
+                rest = after;
+                continue;
+            }
+
+            // A tag runs until the next `]`.
+            let tag = rest[1..].find(']').map(|close| &rest[1..=close]);
+            let parsed = match tag {
+                Some("/") => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                    true
+                }
+                Some(tag) => match parse_tag(tag, fg, bg) {
+                    Some(colors) => {
+                        stack.push(colors);
+                        true
+                    }
+                    None => false,
+                },
+                None => false,
+            };
+            match tag {
+                Some(tag) if parsed => rest = &rest[tag.len() + 2..],
+                _ => {
+                    text = text.span("[", fg, bg);
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        text
+    }
+
+    /// Splits the text into lines of tiles, the same way as `str::lines`.
+    pub fn tile_lines(&self) -> Vec<Vec<TileSprite>> {
+        let mut lines = vec![Vec::new()];
+        for span in self.spans.iter() {
+            for c in span.text.chars() {
+                match c {
+                    '\n' => lines.push(Vec::new()),
+                    '\r' => {}
+                    c => lines
+                        .last_mut()
+                        .unwrap()
+                        .push(TileSprite::new(c, span.fg, span.bg)),
+                }
+            }
+        }
+        if lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines
+    }
+
+    /// Copies any borrowed text, so the result can outlive its source.
+    pub fn into_owned(self) -> StyledText<'static> {
+        StyledText {
+            spans: self
+                .spans
+                .into_iter()
+                .map(|span| Span {
+                    text: Cow::Owned(span.text.into_owned()),
+                    fg: span.fg,
+                    bg: span.bg,
+                })
+                .collect(),
+        }
+    }
+
+    /// The text without colors.
+    pub fn plain(&self) -> String {
+        self.spans.iter().map(|span| &*span.text).collect()
+    }
+}
+
+fn parse_tag(tag: &str, fg: Color, bg: Color) -> Option<(Color, Color)> {
+    if let Some(value) = tag.strip_prefix("bg=") {
+        Some((fg, color(value)?))
+    } else {
+        Some((color(tag.strip_prefix("fg=").unwrap_or(tag))?, bg))
+    }
+}
+
+/// Parses a color name or `#rrggbb` hex color.
+///
+/// The names are `white`, `black`, `gray`, `red`, `orange`, `yellow`,
+/// `green`, `cyan`, `blue`, `purple` and `pink`.
+pub fn color(name: &str) -> Option<Color> {
+    if name.starts_with('#') {
+        return Srgba::hex(name).ok().map(Color::from);
+    }
+
+    Some(match name {
+        "white" => Color::WHITE,
+        "black" => Color::BLACK,
+        "gray" | "grey" => GRAY_300.into(),
+        "red" => RED_400.into(),
+        "orange" => ORANGE_400.into(),
+        "yellow" => YELLOW_400.into(),
+        "green" => GREEN_400.into(),
+        "cyan" => CYAN_400.into(),
+        "blue" => BLUE_400.into(),
+        "purple" => PURPLE_400.into(),
+        "pink" => PINK_400.into(),
+        _ => return None,
+    })
+}
+
+impl<'a> From<&'a str> for StyledText<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::new().push(text)
+    }
+}
+
+impl<'a> From<&'a String> for StyledText<'a> {
+    fn from(text: &'a String) -> Self {
+        Self::new().push(text.as_str())
+    }
+}
+
+impl From<String> for StyledText<'static> {
+    fn from(text: String) -> Self {
+        Self::new().push(text)
+    }
+}
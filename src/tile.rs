@@ -1,22 +1,19 @@
 #![allow(unused)]
 
-use crate::TILE_SIZE;
+use crate::{TILE_SIZE, text::StyledText};
 use bevy::{
-    color::palettes::tailwind::GREEN_300,
+    color::palettes::tailwind::{GREEN_300, YELLOW_300},
     ecs::{
         query::{QueryData, QueryEntityError, QueryFilter, ROQueryItem},
         system::SystemParam,
     },
     platform::collections::{HashMap, hash_map::Entry},
     prelude::*,
-    sprite::Anchor,
 };
 use bevy_query_observer::{AddStartObserver, AddStopObserver, Start, Stop};
 
 pub fn plugin(app: &mut App) {
     app.init_resource::<TileIndex>()
-        .add_systems(PreStartup, spawn_atlas)
-        .add_start_observer(tile_sprite)
         .add_start_observer(TilePosition::update_transform)
         .add_start_observer(TilePosition::observe_insert)
         .add_stop_observer(TilePosition::observe_replace)
@@ -205,7 +202,12 @@ pub struct Wall;
 #[require(TileSprite::FLOOR)]
 pub struct Floor;
 
+/// Leads to the next level. Lies on top of a [`Floor`].
 #[derive(Component)]
+#[require(TileSprite::STAIRS, TileZ(1))]
+pub struct Stairs;
+
+#[derive(Component, Clone, Copy, PartialEq)]
 pub struct TileSprite {
     pub ascii: u8,
     pub fg: Color,
@@ -213,6 +215,15 @@ pub struct TileSprite {
 }
 
 impl TileSprite {
+    /// A tile drawing the atlas glyph for `c`. See [`glyph`](crate::glyph::glyph).
+    pub fn new(c: char, fg: Color, bg: Color) -> Self {
+        Self {
+            ascii: crate::glyph::glyph(c),
+            fg,
+            bg,
+        }
+    }
+
     pub const WALL: Self = Self {
         ascii: b'x',
         fg: Color::Srgba(GREEN_300),
@@ -224,56 +235,12 @@ impl TileSprite {
         fg: Color::Srgba(GREEN_300),
         bg: Color::BLACK,
     };
-}
-
-#[derive(Component)]
-pub struct TileBg;
-
-fn tile_sprite(tiles: Start<(Entity, &TileSprite)>, atlas: Res<TileAtlas>, mut commands: Commands) {
-    let (entity, tile) = tiles.into_inner();
-
-    commands.entity(entity).insert((
-        Sprite {
-            image: atlas.image.clone(),
-            texture_atlas: Some(TextureAtlas {
-                layout: atlas.atlas.clone(),
-                index: tile.ascii as usize,
-            }),
-            color: tile.fg,
-            ..Default::default()
-        },
-        Anchor::BOTTOM_LEFT,
-    ));
-    commands.spawn((
-        TileBg,
-        Transform::from_xyz(0.0, 0.0, -0.01),
-        Sprite::from_color(tile.bg, Vec2::splat(TILE_SIZE as f32)),
-        Anchor::BOTTOM_LEFT,
-        ChildOf(entity),
-    ));
-}
 
-#[derive(Resource)]
-pub struct TileAtlas {
-    pub image: Handle<Image>,
-    pub atlas: Handle<TextureAtlasLayout>,
-}
-
-fn spawn_atlas(
-    mut commands: Commands,
-    server: Res<AssetServer>,
-    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    commands.insert_resource(TileAtlas {
-        image: server.load("anno16.png"),
-        atlas: atlas_layouts.add(TextureAtlasLayout::from_grid(
-            UVec2::splat(TILE_SIZE as u32),
-            16,
-            16,
-            None,
-            None,
-        )),
-    });
+    pub const STAIRS: Self = Self {
+        ascii: b'>',
+        fg: Color::Srgba(YELLOW_300),
+        bg: Color::BLACK,
+    };
 }
 
 #[derive(Clone, Copy)]
@@ -282,8 +249,12 @@ pub enum TextAnchor {
     Center,
 }
 
-pub fn text_tiles(
-    str: &str,
+/// Lays out text as tiles, one tile per character, returning each tile with
+/// its position in pixels.
+///
+/// Accepts plain strings or [`StyledText`] for colored words.
+pub fn text_tiles<'a>(
+    text: impl Into<StyledText<'a>>,
     x: i32,
     y: i32,
     anchor: TextAnchor,
@@ -291,13 +262,16 @@ pub fn text_tiles(
     let tx = x as f32;
     let ty = y as f32;
 
-    let lines = str.lines().count();
-    str.lines().enumerate().flat_map(move |(y, line)| {
-        line.as_bytes().iter().enumerate().map(move |(x, byte)| {
+    let lines = text.into().tile_lines();
+
+    let count = lines.len();
+    lines.into_iter().enumerate().flat_map(move |(y, line)| {
+        let len = line.len();
+        line.into_iter().enumerate().map(move |(x, tile)| {
             let position = match anchor {
                 TextAnchor::Center => Vec2::new(
-                    (x as f32 + tx - (line.len() / 2) as f32) * TILE_SIZE as f32,
-                    (-(y as f32) + ty + (lines / 2) as f32) * TILE_SIZE as f32,
+                    (x as f32 + tx - (len / 2) as f32) * TILE_SIZE as f32,
+                    (-(y as f32) + ty + (count / 2) as f32) * TILE_SIZE as f32,
                 ),
                 TextAnchor::TopLeft => Vec2::new(
                     (x as f32 + tx) * TILE_SIZE as f32,
@@ -305,14 +279,7 @@ pub fn text_tiles(
                 ),
             };
 
-            (
-                TileSprite {
-                    ascii: *byte,
-                    fg: Color::WHITE,
-                    bg: Color::BLACK,
-                },
-                position,
-            )
+            (tile, position)
         })
     })
 }
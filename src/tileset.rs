@@ -0,0 +1,279 @@
+//! Glyph atlases for drawing tiles without Bevy sprites, decoded from PNG or
+//! PPM files.
+
+use crate::TILE_SIZE;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// Loads [`Tileset`] assets from `.png` and `.ppm` files.
+///
+/// Added by every plugin that needs a tileset, so check
+/// [`App::is_plugin_added`] first.
+pub struct TilesetPlugin;
+
+impl Plugin for TilesetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tileset>()
+            .init_asset_loader::<TilesetLoader>();
+    }
+}
+
+/// The atlas of the default font, relative to the assets folder.
+pub const TILESET_PATH: &str = "anno16.png";
+
+/// A glyph atlas decoded to 8-bit sRGB RGBA pixels.
+#[derive(Asset, TypePath, Clone)]
+pub struct Tileset {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    /// Size of a single glyph in pixels.
+    pub glyph_size: [u32; 2],
+    /// Columns and rows of glyphs in the atlas, at least one each.
+    pub grid: [u32; 2],
+}
+
+#[derive(Debug)]
+pub enum TilesetError {
+    Io(std::io::Error),
+    /// The data is neither a PNG nor a P3 or P6 PPM.
+    UnknownFormat,
+    /// A PPM header field is missing or malformed.
+    Header(&'static str),
+    /// A PPM has fewer samples than its header declares.
+    Truncated,
+    Sample(String),
+    Png(png::DecodingError),
+    /// The image cannot be divided evenly into glyphs of the given size, or
+    /// holds none.
+    GlyphSize {
+        width: u32,
+        height: u32,
+        glyph_size: [u32; 2],
+    },
+}
+
+impl core::fmt::Display for TilesetError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::UnknownFormat => write!(f, "tileset is not a PNG or a P3/P6 PPM"),
+            Self::Header(field) => write!(f, "invalid PPM header: bad {field}"),
+            Self::Truncated => write!(f, "PPM pixel data is truncated"),
+            Self::Sample(sample) => write!(f, "invalid PPM sample `{sample}`"),
+            Self::Png(e) => e.fmt(f),
+            Self::GlyphSize {
+                width,
+                height,
+                glyph_size: [w, h],
+            } => write!(
+                f,
+                "a {width}x{height} tileset cannot be divided into {w}x{h} glyphs"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for TilesetError {}
+
+impl From<png::DecodingError> for TilesetError {
+    fn from(e: png::DecodingError) -> Self {
+        Self::Png(e)
+    }
+}
+
+impl Tileset {
+    /// Decodes a PNG or PPM atlas made of `glyph_size` glyphs, laid out in
+    /// rows from the top left.
+    pub fn decode(bytes: &[u8], glyph_size: [u32; 2]) -> Result<Self, TilesetError> {
+        let (width, height, rgba) = if bytes.starts_with(b"\x89PNG") {
+            decode_png(bytes)?
+        } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+            decode_ppm(bytes)?
+        } else {
+            return Err(TilesetError::UnknownFormat);
+        };
+
+        let [w, h] = glyph_size;
+        if w == 0 || h == 0 || width < w || height < h || width % w != 0 || height % h != 0 {
+            return Err(TilesetError::GlyphSize {
+                width,
+                height,
+                glyph_size,
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rgba,
+            glyph_size,
+            grid: [width / w, height / h],
+        })
+    }
+}
+
+/// The size of the glyphs of a [`Tileset`] asset.
+#[derive(Serialize, Deserialize)]
+pub struct TilesetSettings {
+    pub glyph_size: [u32; 2],
+}
+
+impl Default for TilesetSettings {
+    fn default() -> Self {
+        Self {
+            glyph_size: [TILE_SIZE as u32; 2],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TilesetLoader;
+
+impl AssetLoader for TilesetLoader {
+    type Asset = Tileset;
+    type Settings = TilesetSettings;
+    type Error = TilesetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &TilesetSettings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Tileset, TilesetError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(TilesetError::Io)?;
+        Tileset::decode(&bytes, settings.glyph_size)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "ppm"]
+    }
+}
+
+fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), TilesetError> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        // Indexed images are expanded by `normalize_to_color8`.
+        png::ColorType::Grayscale | png::ColorType::Indexed => {
+            buffer.iter().flat_map(|&v| [v, v, v, 255]).collect()
+        }
+    };
+    Ok((info.width, info.height, rgba))
+}
+
+/// Reads whitespace separated header tokens, skipping `#` comments.
+struct PpmHeader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmHeader<'a> {
+    fn token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.position)? {
+                b'#' => {
+                    while self.bytes.get(self.position).is_some_and(|b| *b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+        {
+            self.position += 1;
+        }
+        Some(&self.bytes[start..self.position])
+    }
+
+    fn number(&mut self, field: &'static str) -> Result<u32, TilesetError> {
+        self.token()
+            .and_then(|token| core::str::from_utf8(token).ok()?.parse().ok())
+            .ok_or(TilesetError::Header(field))
+    }
+}
+
+fn decode_ppm(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), TilesetError> {
+    let mut header = PpmHeader { bytes, position: 0 };
+    let binary = header.token() == Some(b"P6");
+    let width = header.number("width")?;
+    let height = header.number("height")?;
+    let maxval = header.number("maxval")?;
+    if !(1..=u16::MAX as u32).contains(&maxval) {
+        return Err(TilesetError::Header("maxval"));
+    }
+
+    let samples = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or(TilesetError::Header("size"))?;
+    let scale = |value: u32| ((value.min(maxval) * 255 + maxval / 2) / maxval) as u8;
+
+    let rgb = if binary {
+        // Exactly one whitespace byte separates the header from the pixels,
+        // which may themselves contain whitespace values.
+        let data = bytes
+            .get(header.position + 1..)
+            .ok_or(TilesetError::Truncated)?;
+        if maxval < 256 {
+            let data = data.get(..samples).ok_or(TilesetError::Truncated)?;
+            data.iter().map(|&v| scale(v as u32)).collect::<Vec<_>>()
+        } else {
+            let data = data.get(..samples * 2).ok_or(TilesetError::Truncated)?;
+            data.chunks_exact(2)
+                .map(|v| scale(u16::from_be_bytes([v[0], v[1]]) as u32))
+                .collect()
+        }
+    } else {
+        // Every sample but the last is followed by at least one whitespace
+        // byte, so check the declared count before trusting it to allocate.
+        let remaining = bytes.len() - header.position;
+        if samples > remaining.div_ceil(2) {
+            return Err(TilesetError::Truncated);
+        }
+        let mut rgb = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let token = header.token().ok_or(TilesetError::Truncated)?;
+            let value = core::str::from_utf8(token)
+                .ok()
+                .and_then(|token| token.parse::<u32>().ok())
+                .ok_or_else(|| TilesetError::Sample(String::from_utf8_lossy(token).into()))?;
+            rgb.push(scale(value));
+        }
+        rgb
+    };
+
+    let rgba = rgb
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect();
+    Ok((width, height, rgba))
+}
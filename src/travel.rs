@@ -0,0 +1,200 @@
+//! Walking the player to a tile one step at a time, for click-to-travel.
+
+use crate::{
+    GameState,
+    enemy::Enemy,
+    input::Move,
+    mapgen::Descended,
+    panel,
+    player::Player,
+    tile::{MoveIntent, PositionQuery, Solid, TilePosition},
+    view::{MapBounds, Viewport},
+};
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_enhanced_input::prelude::*;
+use std::collections::VecDeque;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        travel
+            .run_if(in_state(GameState::Overworld))
+            .run_if(panel::no_modal),
+    )
+    .add_systems(OnExit(GameState::Overworld), stop_travel)
+    .add_observer(start_travel)
+    .add_observer(arrive)
+    .add_observer(interrupt_travel);
+}
+
+/// Seconds between two steps of a [`Travel`].
+const STEP_SECS: f32 = 0.1;
+
+/// Asks the player to walk to `target`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TravelTo {
+    pub target: IVec2,
+}
+
+/// The remaining steps of a walk started with [`TravelTo`].
+///
+/// Travel stops when a step is blocked, when the player moves by hand, and
+/// when an enemy that was not visible at the start comes into view.
+#[derive(Component, Debug)]
+pub struct Travel {
+    path: VecDeque<IVec2>,
+    timer: Timer,
+    /// Enemies that were already on screen when the travel started.
+    seen: HashSet<Entity>,
+}
+
+/// The shortest path of orthogonal steps from `from` to `to` that avoids
+/// cells where `is_solid` holds, excluding `from`. `to` itself may be solid,
+/// so walking to an enemy ends by bumping into it.
+pub fn find_path(
+    from: IVec2,
+    to: IVec2,
+    bounds: IRect,
+    is_solid: impl Fn(IVec2) -> bool,
+) -> Option<Vec<IVec2>> {
+    const STEPS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+    let mut came_from = HashMap::from([(from, from)]);
+    let mut frontier = VecDeque::from([from]);
+
+    while let Some(current) = frontier.pop_front() {
+        if current == to {
+            let mut path = vec![current];
+            let mut cell = current;
+            while let Some(&previous) = came_from.get(&cell)
+                && previous != from
+            {
+                path.push(previous);
+                cell = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for step in STEPS {
+            let next = current + step;
+            if !bounds.contains(next) || came_from.contains_key(&next) {
+                continue;
+            }
+            if next != to && is_solid(next) {
+                continue;
+            }
+            came_from.insert(next, current);
+            frontier.push_back(next);
+        }
+    }
+
+    None
+}
+
+fn visible_enemies(
+    viewport: &Viewport,
+    enemies: &Query<(Entity, &TilePosition), With<Enemy>>,
+) -> HashSet<Entity> {
+    enemies
+        .iter()
+        .filter(|(_, position)| viewport.contains(position.0))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+fn start_travel(
+    trigger: On<TravelTo>,
+    player: Single<(Entity, &TilePosition), With<Player>>,
+    solid: PositionQuery<(), With<Solid>>,
+    enemies: Query<(Entity, &TilePosition), With<Enemy>>,
+    viewport: Res<Viewport>,
+    bounds: Option<Res<MapBounds>>,
+    state: Res<State<GameState>>,
+    mut commands: Commands,
+) {
+    let (entity, position) = *player;
+    if *state.get() != GameState::Overworld || position.0 == trigger.target {
+        return;
+    }
+
+    let bounds = bounds.map_or(viewport.rect(), |bounds| bounds.0);
+    let is_solid = |cell| solid.iter(&TilePosition(cell)).next().is_some();
+    let Some(path) = find_path(position.0, trigger.target, bounds, is_solid) else {
+        info!("no path to {}", trigger.target);
+        return;
+    };
+
+    let mut timer = Timer::from_seconds(STEP_SECS, TimerMode::Repeating);
+    // Take the first step right away.
+    timer.set_elapsed(timer.duration());
+    commands.entity(entity).insert(Travel {
+        path: path.into(),
+        timer,
+        seen: visible_enemies(&viewport, &enemies),
+    });
+}
+
+fn travel(
+    mut commands: Commands,
+    time: Res<Time>,
+    viewport: Res<Viewport>,
+    player: Option<Single<(Entity, &TilePosition, &mut Travel), With<Player>>>,
+    enemies: Query<(Entity, &TilePosition), With<Enemy>>,
+) {
+    let Some(player) = player else {
+        return;
+    };
+    let (entity, position, mut travel) = player.into_inner();
+
+    if visible_enemies(&viewport, &enemies)
+        .iter()
+        .any(|enemy| !travel.seen.contains(enemy))
+    {
+        info!("travel interrupted, an enemy came into view");
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    travel.timer.tick(time.delta());
+    if !travel.timer.just_finished() {
+        return;
+    }
+
+    let Some(&next) = travel.path.front() else {
+        commands.entity(entity).remove::<Travel>();
+        return;
+    };
+    // The last step did not land, so something is in the way.
+    if (next - position.0).abs().element_sum() != 1 {
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    travel.path.pop_front();
+    commands
+        .entity(entity)
+        .insert(MoveIntent(next - position.0));
+}
+
+fn interrupt_travel(
+    _: On<Start<Move>>,
+    player: Single<Entity, (With<Player>, With<Travel>)>,
+    mut commands: Commands,
+) {
+    commands.entity(*player).remove::<Travel>();
+}
+
+/// Paths do not carry over to the next level.
+fn arrive(_: On<Descended>, commands: Commands, travel: Query<Entity, With<Travel>>) {
+    stop_travel(commands, travel);
+}
+
+fn stop_travel(mut commands: Commands, travel: Query<Entity, With<Travel>>) {
+    for entity in travel.iter() {
+        commands.entity(entity).remove::<Travel>();
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{HEIGHT, TILE_SIZE, WIDTH, player::Player, tile::TilePosition};
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Viewport>()
+        .init_resource::<CameraFollow>()
+        .add_systems(PostUpdate, follow_player);
+}
+
+/// The tiles currently on screen.
+///
+/// Screen space tiles, which have a [`Transform`] but no [`TilePosition`],
+/// are placed relative to the center of the viewport.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub center: IVec2,
+    /// Size of the viewport in tiles.
+    pub size: UVec2,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            center: IVec2::ZERO,
+            size: UVec2::new((WIDTH / TILE_SIZE) as u32, (HEIGHT / TILE_SIZE) as u32),
+        }
+    }
+}
+
+impl Viewport {
+    /// The visible tiles, including the `max` corner.
+    pub fn rect(&self) -> IRect {
+        let min = self.center - (self.size / 2).as_ivec2();
+        IRect {
+            min,
+            max: min + self.size.as_ivec2() - IVec2::ONE,
+        }
+    }
+
+    pub fn contains(&self, position: IVec2) -> bool {
+        self.rect().contains(position)
+    }
+}
+
+/// How the [`Viewport`] follows the [`Player`].
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct CameraFollow {
+    /// Size in tiles of the area around the center the player can move in
+    /// without scrolling the viewport.
+    pub deadzone: UVec2,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            deadzone: UVec2::new(16, 12),
+        }
+    }
+}
+
+/// The tiles of the current map, including the `max` corner. The
+/// [`Viewport`] does not scroll past them.
+#[derive(Resource, Debug, Clone, Copy, Deref)]
+pub struct MapBounds(pub IRect);
+
+pub(crate) fn follow_player(
+    mut viewport: ResMut<Viewport>,
+    follow: Res<CameraFollow>,
+    bounds: Option<Res<MapBounds>>,
+    player: Option<Single<&TilePosition, With<Player>>>,
+) {
+    let mut center = viewport.center;
+
+    if let Some(player) = player {
+        let half = (follow.deadzone / 2).as_ivec2();
+        center = center.clamp(player.0 - half, player.0 + half);
+    }
+
+    if let Some(bounds) = bounds {
+        let half = (viewport.size / 2).as_ivec2();
+        let min = bounds.min + half;
+        let max = bounds.max + IVec2::ONE - (viewport.size.as_ivec2() - half);
+        // Maps smaller than the viewport stay centered.
+        center = IVec2::new(
+            if min.x <= max.x {
+                center.x.clamp(min.x, max.x)
+            } else {
+                (bounds.min.x + bounds.max.x + 1) / 2
+            },
+            if min.y <= max.y {
+                center.y.clamp(min.y, max.y)
+            } else {
+                (bounds.min.y + bounds.max.y + 1) / 2
+            },
+        );
+    }
+
+    if viewport.center != center {
+        viewport.center = center;
+    }
+}
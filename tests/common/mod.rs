@@ -0,0 +1,17 @@
+//! Checked-in fixtures for golden tests.
+//!
+//! After an intended change, rewrite the fixtures with `BLESS=1 cargo test`
+//! and review their diff.
+
+use std::path::PathBuf;
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// Whether fixtures are rewritten instead of compared with.
+pub fn bless() -> bool {
+    std::env::var_os("BLESS").is_some()
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use dung::{
+    controls::{CONTROLS_PATH, CONTROLS_VERSION, Control, ControlInput, Controls, ControlsError},
+    headless_app, headless_app_in,
+};
+
+/// Writes `ron` to a file of its own and loads it as [`Controls`].
+fn load(name: &str, ron: &str) -> Result<Controls, ControlsError> {
+    let path = std::env::temp_dir().join(format!("dung-{}-{name}.ron", std::process::id()));
+    std::fs::write(&path, ron).unwrap();
+    let controls = Controls::load(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    controls
+}
+
+#[test]
+fn conflicts_within_overlapping_contexts() {
+    let controls = Controls::default();
+    // Both are read in the inventory.
+    assert_eq!(
+        controls.conflicts(Control::DropItem, ControlInput::Key(KeyCode::KeyI)),
+        [Control::OpenInventory]
+    );
+    // Global controls overlap every context.
+    assert_eq!(
+        controls.conflicts(Control::Save, ControlInput::Key(KeyCode::KeyW)),
+        [Control::MoveUp]
+    );
+}
+
+#[test]
+fn no_conflicts_across_disjoint_contexts() {
+    let controls = Controls::default();
+    // W moves in the overworld, which menus never read at the same time.
+    assert!(
+        controls
+            .conflicts(Control::NavigateUp, ControlInput::Key(KeyCode::KeyW))
+            .is_empty()
+    );
+    // A control never conflicts with itself.
+    assert!(
+        controls
+            .conflicts(Control::MoveUp, ControlInput::Key(KeyCode::KeyW))
+            .is_empty()
+    );
+}
+
+#[test]
+fn rebind_replaces_only_the_slot() {
+    let mut controls = Controls::default();
+    controls
+        .rebind(Control::MoveUp, 0, ControlInput::Key(KeyCode::KeyK))
+        .unwrap();
+    assert_eq!(
+        controls.inputs(Control::MoveUp),
+        [
+            ControlInput::Key(KeyCode::KeyK),
+            ControlInput::Key(KeyCode::ArrowUp)
+        ]
+    );
+
+    controls
+        .rebind(
+            Control::MoveUp,
+            2,
+            ControlInput::Gamepad(GamepadButton::DPadUp),
+        )
+        .unwrap();
+    assert_eq!(controls.inputs(Control::MoveUp).len(), 3);
+
+    assert_eq!(
+        controls.rebind(Control::MoveUp, 0, ControlInput::Key(KeyCode::KeyI)),
+        Err(vec![Control::OpenInventory])
+    );
+    assert_eq!(
+        controls.inputs(Control::MoveUp)[0],
+        ControlInput::Key(KeyCode::KeyK)
+    );
+}
+
+#[test]
+fn load_keeps_defaults_of_missing_controls() {
+    let ron = format!("(version: {CONTROLS_VERSION}, bindings: {{ MoveUp: [Key(KeyK)] }})");
+    let controls = load("merge", &ron).unwrap();
+
+    assert_eq!(
+        controls.inputs(Control::MoveUp),
+        [ControlInput::Key(KeyCode::KeyK)]
+    );
+    let defaults = Controls::default();
+    for control in Control::ALL {
+        if control != Control::MoveUp {
+            assert_eq!(controls.inputs(control), defaults.inputs(control));
+        }
+    }
+}
+
+#[test]
+fn load_rejects_other_versions() {
+    let ron = format!("(version: {}, bindings: {{}})", CONTROLS_VERSION + 1);
+    assert!(matches!(
+        load("version", &ron),
+        Err(ControlsError::Version(version)) if version == CONTROLS_VERSION + 1
+    ));
+}
+
+#[test]
+fn apps_read_controls_from_their_data_dir() {
+    let dir = std::env::temp_dir().join(format!("dung-{}-data-dir", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let ron = format!("(version: {CONTROLS_VERSION}, bindings: {{ MoveUp: [Key(KeyK)] }})");
+    std::fs::write(dir.join(CONTROLS_PATH), ron).unwrap();
+
+    let app = headless_app_in(7, &dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        app.world().resource::<Controls>().inputs(Control::MoveUp),
+        [ControlInput::Key(KeyCode::KeyK)]
+    );
+
+    // Other apps start from the defaults, whatever the working directory has.
+    let app = headless_app(7);
+    assert_eq!(
+        app.world().resource::<Controls>().inputs(Control::MoveUp),
+        Controls::default().inputs(Control::MoveUp)
+    );
+}
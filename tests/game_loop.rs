@@ -0,0 +1,82 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use dung::{
+    GameState,
+    enemy::Enemy,
+    headless_app,
+    level::Experience,
+    player::Player,
+    tile::TilePosition,
+    travel::{Travel, TravelTo},
+};
+use std::time::Duration;
+
+fn state(app: &App) -> GameState {
+    *app.world().resource::<State<GameState>>().get()
+}
+
+/// Presses and releases `key` over two frames.
+fn tap(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+    app.update();
+}
+
+/// Walks up to the droid, fights it with the default controls and wins, all
+/// without a window.
+#[test]
+fn walk_fight_and_win() {
+    let mut app = headless_app(7);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        50,
+    )));
+    app.update();
+
+    // The droid wanders, so the player keeps walking to where it is now.
+    for _ in 0..2000 {
+        if state(&app) != GameState::Overworld {
+            break;
+        }
+        let world = app.world_mut();
+        let traveling = world
+            .query_filtered::<(), (With<Player>, With<Travel>)>()
+            .single(world)
+            .is_ok();
+        let droid = world
+            .query_filtered::<&TilePosition, With<Enemy>>()
+            .single(world)
+            .map(|position| position.0);
+        if !traveling && let Ok(target) = droid {
+            world.trigger(TravelTo { target });
+        }
+        app.update();
+    }
+    assert_eq!(state(&app), GameState::Arena);
+
+    // Attack, the first option, until the fight is over.
+    for _ in 0..100 {
+        if state(&app) != GameState::Arena {
+            break;
+        }
+        tap(&mut app, KeyCode::Enter);
+    }
+    assert_ne!(state(&app), GameState::Arena);
+
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .query_filtered::<(), With<Enemy>>()
+            .iter(world)
+            .count(),
+        0
+    );
+    let experience = world
+        .query_filtered::<&Experience, With<Player>>()
+        .single(world)
+        .unwrap();
+    assert!(experience.xp > 0 || experience.level > 1);
+}
@@ -0,0 +1,63 @@
+//! Golden tests: the tiles of a headless game as ASCII, compared with the
+//! fixtures in `tests/fixtures`.
+//!
+//! After an intended change to map generation or a panel, rewrite the
+//! fixtures with `BLESS=1 cargo test --test golden` and review their diff.
+
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use dung::{GameState, headless_app, snapshot::TileGrid, view::MapBounds, view::Viewport};
+use std::time::Duration;
+
+fn assert_golden(name: &str, actual: &str) {
+    let path = common::fixture_path(name);
+    if common::bless() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!("failed to read {}: {e}", path.display());
+    });
+    assert!(
+        expected == actual,
+        "{} does not match, got:\n{actual}",
+        path.display()
+    );
+}
+
+/// A headless game that does not advance time, so nothing walks.
+fn frozen_app(seed: u64) -> App {
+    let mut app = headless_app(seed);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    app.update();
+    app.update();
+    app
+}
+
+fn map_layout(seed: u64) -> String {
+    let mut app = frozen_app(seed);
+    let bounds = app.world().resource::<MapBounds>().0;
+    TileGrid::capture_region(app.world_mut(), bounds).to_ascii()
+}
+
+#[test]
+fn mapgen_layout() {
+    assert_golden("mapgen_seed_7.txt", &map_layout(7));
+    assert_golden("mapgen_seed_42.txt", &map_layout(42));
+}
+
+#[test]
+fn arena_layout() {
+    let mut app = frozen_app(7);
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Arena);
+    app.update();
+    app.update();
+
+    let viewport = *app.world().resource::<Viewport>();
+    let screen = TileGrid::capture_screen(app.world_mut(), viewport);
+    assert_golden("arena_seed_7.txt", &screen.to_ascii());
+}
@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use dung::{
+    layout::{Canvas, Widget},
+    text::StyledText,
+};
+
+/// The rows of a canvas as text, without trailing blanks.
+fn rows(canvas: &Canvas) -> Vec<String> {
+    canvas
+        .cells
+        .chunks(canvas.size.x as usize)
+        .map(|row| {
+            let row = row
+                .iter()
+                .map(|tile| tile.ascii as char)
+                .collect::<String>();
+            row.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn paragraphs_break_at_spaces() {
+    let canvas = Widget::paragraph("the quick brown fox").render(10);
+    assert_eq!(rows(&canvas), ["the quick", "brown fox"]);
+}
+
+#[test]
+fn words_longer_than_a_line_are_broken_up() {
+    let canvas = Widget::paragraph("a abcdefghij").render(4);
+    assert_eq!(rows(&canvas), ["a", "abcd", "efgh", "ij"]);
+}
+
+#[test]
+fn paragraphs_keep_line_breaks() {
+    let canvas = Widget::paragraph("one\ntwo three").render(20);
+    assert_eq!(rows(&canvas), ["one", "two three"]);
+}
+
+#[test]
+fn wrapped_spaces_keep_their_colors() {
+    let highlight = Color::srgb(0.0, 0.0, 1.0);
+    let text = StyledText::new()
+        .push("plain ")
+        .span("two words", Color::WHITE, highlight);
+    let canvas = Widget::paragraph(text).render(20);
+
+    assert_eq!(rows(&canvas), ["plain two words"]);
+    // The space inside the highlighted span is highlighted too.
+    assert_eq!(canvas.cells[9].bg, highlight);
+    assert_eq!(canvas.cells[5].bg, StyledText::BG);
+}
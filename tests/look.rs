@@ -0,0 +1,61 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use dung::{
+    headless_app,
+    player::Player,
+    snapshot::TileGrid,
+    tile::{TilePosition, TileZ},
+    view::Viewport,
+};
+use std::time::Duration;
+
+/// Presses and releases `key` over two frames.
+fn tap(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+    app.update();
+}
+
+fn screen(app: &mut App) -> String {
+    let viewport = *app.world().resource::<Viewport>();
+    TileGrid::capture_screen(app.world_mut(), viewport).to_ascii()
+}
+
+#[test]
+fn look_cycles_through_stacked_entities_from_the_top() {
+    let mut app = headless_app(7);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    app.update();
+
+    // A crate between the player and the floor below them.
+    let player = *app
+        .world_mut()
+        .query_filtered::<&TilePosition, With<Player>>()
+        .single(app.world())
+        .unwrap();
+    app.world_mut()
+        .spawn((Name::new("Crate"), player, TileZ(1)));
+    app.update();
+
+    tap(&mut app, KeyCode::KeyL);
+    let ascii = screen(&mut app);
+    assert!(ascii.contains("1/3"), "{ascii}");
+    // The list below the description goes from the top of the stack down.
+    let list = &ascii[ascii.rfind("You").unwrap()..];
+    let crate_row = list.find("Crate").unwrap();
+    assert!(list[crate_row..].contains("Floor"), "{ascii}");
+
+    tap(&mut app, KeyCode::Tab);
+    let ascii = screen(&mut app);
+    assert!(ascii.contains("2/3"), "{ascii}");
+    // The crate is described above the list.
+    assert!(ascii.find("Crate") < ascii.rfind("You"), "{ascii}");
+
+    tap(&mut app, KeyCode::Tab);
+    tap(&mut app, KeyCode::Tab);
+    assert!(screen(&mut app).contains("1/3"));
+}
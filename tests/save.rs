@@ -0,0 +1,134 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use dung::{
+    DataDir, headless_app,
+    item::Item,
+    loot::LootRoll,
+    player::Player,
+    save::{self, ActorKind, SaveFile},
+    stats::{Health, Modifiers, Stats},
+    tile::TilePosition,
+};
+use std::time::Duration;
+
+/// Updates `app` until the item assets are loaded, every item is resolved and
+/// all loot is rolled.
+fn wait_for_items(app: &mut App) {
+    for _ in 0..5000 {
+        app.update();
+        let world = app.world_mut();
+        let items = world.query::<&Item>().iter(world).count();
+        let unresolved = world
+            .query_filtered::<(), (With<Item>, Without<Modifiers>)>()
+            .iter(world)
+            .count();
+        let rolls = world.query::<&LootRoll>().iter(world).count();
+        if items > 0 && unresolved == 0 && rolls == 0 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("item assets did not load");
+}
+
+/// The save as RON, with actors and ground items in a stable order.
+fn normalized(mut save: SaveFile) -> String {
+    save.actors
+        .sort_by_key(|actor| (actor.kind == ActorKind::Droid, actor.position));
+    save.ground.sort_by_key(|(position, _)| *position);
+    ron::ser::to_string_pretty(&save, Default::default()).unwrap()
+}
+
+fn player_health(app: &mut App) -> (i32, i32) {
+    let world = app.world_mut();
+    let (health, stats) = world
+        .query_filtered::<(&Health, &Stats), With<Player>>()
+        .single(world)
+        .unwrap();
+    (health.0, stats.max_health)
+}
+
+/// The number of entities on the map and of items.
+fn entity_counts(app: &mut App) -> (usize, usize) {
+    let world = app.world_mut();
+    let on_map = world
+        .query_filtered::<(), With<TilePosition>>()
+        .iter(world)
+        .count();
+    let items = world
+        .query_filtered::<(), Or<(With<Item>, With<LootRoll>)>>()
+        .iter(world)
+        .count();
+    (on_map, items)
+}
+
+#[test]
+fn save_load_round_trip() {
+    let mut app = headless_app(7);
+    // Freeze time so nothing walks or draws from the RNG between saves.
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    wait_for_items(&mut app);
+
+    // The player starts with a health unit equipped. Take enough damage to be
+    // below the max health it grants but keep more than the base max health.
+    let (_, max_health) = player_health(&mut app);
+    let world = app.world_mut();
+    world
+        .query_filtered::<&mut Health, With<Player>>()
+        .single_mut(world)
+        .unwrap()
+        .0 = max_health - 1;
+    app.update();
+    assert_eq!(player_health(&mut app), (max_health - 1, max_health));
+
+    let counts = entity_counts(&mut app);
+    let before = save::save(app.world_mut()).unwrap();
+    let ron = ron::ser::to_string_pretty(&before, Default::default()).unwrap();
+    let restored = ron::from_str::<SaveFile>(&ron).unwrap();
+    save::load(app.world_mut(), restored).unwrap();
+    // Stats are recomputed after the load and again on the next frame.
+    app.update();
+    app.update();
+
+    assert_eq!(player_health(&mut app), (max_health - 1, max_health));
+    // Nothing on the map or in an inventory is lost or duplicated.
+    assert_eq!(entity_counts(&mut app), counts);
+    let after = save::save(app.world_mut()).unwrap();
+    assert_eq!(normalized(before), normalized(after));
+}
+
+#[test]
+fn load_rejects_other_versions() {
+    let mut app = headless_app(7);
+    app.update();
+
+    let mut save = save::save(app.world_mut()).unwrap();
+    save.version = save::SAVE_VERSION + 1;
+    assert!(matches!(
+        save::load(app.world_mut(), save),
+        Err(save::SaveError::Version(_))
+    ));
+}
+
+#[test]
+fn save_files_go_to_the_data_dir() {
+    let mut app = headless_app(7);
+    app.update();
+
+    let path = app.world().resource::<DataDir>().0.join(save::SAVE_PATH);
+    save::save_to_file(app.world_mut(), &path).unwrap();
+    save::load_from_file(app.world_mut(), &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn save_rejects_unknown_entities_on_the_map() {
+    let mut app = headless_app(7);
+    app.update();
+
+    app.world_mut()
+        .spawn((Name::new("Totem"), TilePosition::new(3, 3)));
+    assert!(matches!(
+        save::save(app.world_mut()),
+        Err(save::SaveError::Unsaved(entities)) if entities == ["Totem"]
+    ));
+}
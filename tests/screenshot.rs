@@ -0,0 +1,94 @@
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use dung::{
+    GameState, headless_app,
+    screenshot::{self, Screenshot},
+    snapshot::TileGrid,
+    tile::TileSprite,
+    tileset::Tileset,
+};
+use std::time::Duration;
+
+/// A 2x1 RGBA PNG.
+fn png(rgba: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(rgba).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+#[test]
+fn glyphs_are_tinted_over_the_background() {
+    // An opaque white glyph and a transparent one.
+    let tileset = Tileset::decode(&png(&[255, 255, 255, 255, 0, 0, 0, 0]), [1, 1]).unwrap();
+    let tile = |ascii| TileSprite {
+        ascii,
+        fg: Color::srgb(1.0, 0.0, 0.0),
+        bg: Color::srgb(0.0, 0.0, 1.0),
+    };
+    let grid = TileGrid {
+        origin: IVec2::ZERO,
+        size: UVec2::new(3, 1),
+        // Glyphs wrap around the atlas, so 3 is the transparent glyph.
+        cells: vec![Some(tile(0)), Some(tile(3)), None],
+    };
+
+    let screenshot = Screenshot::rasterize(&grid, &tileset);
+    assert_eq!((screenshot.width, screenshot.height), (3, 1));
+    assert_eq!(
+        screenshot.rgba,
+        [255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 0, 255]
+    );
+}
+
+#[test]
+fn tilesets_without_a_whole_glyph_are_rejected() {
+    assert!(Tileset::decode(b"P3 0 0 255", [1, 1]).is_err());
+    assert!(Tileset::decode(&png(&[0; 8]), [4, 1]).is_err());
+}
+
+/// Compares the arena screen with a checked-in PNG, so changes to the font,
+/// the colors or the blending show up.
+#[test]
+fn arena_screenshot() {
+    let mut app = headless_app(7);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    app.update();
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Arena);
+    app.update();
+    app.update();
+
+    let mut actual = None;
+    for _ in 0..5000 {
+        app.update();
+        actual = screenshot::capture(app.world_mut());
+        if actual.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let actual = actual.expect("the tileset did not load");
+
+    let path = common::fixture_path("arena_seed_7.png");
+    if common::bless() {
+        actual.save(&path).unwrap();
+        return;
+    }
+    let expected = Tileset::decode(&std::fs::read(&path).unwrap(), [1, 1]).unwrap();
+    assert_eq!(
+        (expected.width, expected.height),
+        (actual.width, actual.height)
+    );
+    assert!(
+        expected.rgba == actual.rgba,
+        "{} does not match",
+        path.display()
+    );
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use dung::{
+    arena::Blocking,
+    headless_app,
+    stats::{self, BaseStats, Modifier, Modifiers, Stat, Stats, StatusEffectOf},
+};
+
+fn defense(app: &mut App, entity: Entity) -> i32 {
+    app.world().get::<Stats>(entity).unwrap().defense
+}
+
+#[test]
+fn blocking_reduces_damage() {
+    let mut app = headless_app(7);
+    app.update();
+
+    // The player with the starting shield.
+    let player = app
+        .world_mut()
+        .spawn((
+            BaseStats::PLAYER,
+            Modifiers(vec![Modifier::add(Stat::Defense, 10)]),
+        ))
+        .id();
+    app.update();
+    let attack = BaseStats::DROID.attack;
+    let hit = stats::damage(attack, defense(&mut app, player));
+
+    app.world_mut().spawn((Blocking, StatusEffectOf(player)));
+    app.update();
+    let blocked = stats::damage(attack, defense(&mut app, player));
+
+    assert_eq!(defense(&mut app, player), 22);
+    assert!(blocked < hit, "blocking {hit} damage left {blocked}");
+    assert!(blocked >= 1);
+}
+
+#[test]
+fn damage_never_drops_below_one() {
+    assert_eq!(stats::damage(1, 1000), 1);
+    assert_eq!(stats::damage(0, 0), 1);
+    assert_eq!(stats::damage(10, 0), 10);
+}
@@ -0,0 +1,67 @@
+use bevy::{
+    color::palettes::tailwind::{BLUE_400, RED_400},
+    prelude::*,
+};
+use dung::text::{Span, StyledText, color};
+
+fn span(text: &str, fg: impl Into<Color>, bg: impl Into<Color>) -> Span<'static> {
+    Span {
+        text: text.to_string().into(),
+        fg: fg.into(),
+        bg: bg.into(),
+    }
+}
+
+#[test]
+fn double_brackets_are_literal() {
+    let text = StyledText::markup("[[red] a[[b");
+    assert_eq!(text.plain(), "[red] a[b");
+    assert!(
+        text.spans
+            .iter()
+            .all(|span| span.fg == StyledText::FG && span.bg == StyledText::BG)
+    );
+}
+
+#[test]
+fn nested_tags_restore_the_enclosing_colors() {
+    let text = StyledText::markup("a[red]b[bg=blue]c[/]d[/]e");
+    assert_eq!(
+        text.spans,
+        [
+            span("a", StyledText::FG, StyledText::BG),
+            span("b", RED_400, StyledText::BG),
+            span("c", RED_400, BLUE_400),
+            span("d", RED_400, StyledText::BG),
+            span("e", StyledText::FG, StyledText::BG),
+        ]
+    );
+}
+
+#[test]
+fn unbalanced_closing_tags_are_ignored() {
+    let text = StyledText::markup("[/]a");
+    assert_eq!(text.spans, [span("a", StyledText::FG, StyledText::BG)]);
+}
+
+#[test]
+fn hex_colors() {
+    let orange = Color::from(Srgba::rgb_u8(0xff, 0x80, 0x00));
+    assert_eq!(color("#ff8000"), Some(orange));
+
+    let text = StyledText::markup("[fg=#ff8000]x[bg=#000000]y");
+    assert_eq!(
+        text.spans,
+        [
+            span("x", orange, StyledText::BG),
+            span("y", orange, Color::from(Srgba::rgb_u8(0, 0, 0))),
+        ]
+    );
+}
+
+#[test]
+fn malformed_tags_are_drawn_as_is() {
+    assert_eq!(StyledText::markup("[nope]x").plain(), "[nope]x");
+    assert_eq!(StyledText::markup("[#zzzzzz]x").plain(), "[#zzzzzz]x");
+    assert_eq!(StyledText::markup("[red").plain(), "[red");
+}
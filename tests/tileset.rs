@@ -0,0 +1,101 @@
+use dung::tileset::{Tileset, TilesetError};
+
+fn decode(bytes: &[u8]) -> Result<Tileset, TilesetError> {
+    Tileset::decode(bytes, [1, 1])
+}
+
+#[test]
+fn p3_skips_comments() {
+    let tileset =
+        decode(b"P3 # plain\n2 # width\n1\n# maxval\n255\n255 0 0 # red\n0 0 255\n").unwrap();
+    assert_eq!((tileset.width, tileset.height), (2, 1));
+    assert_eq!(tileset.rgba, [255, 0, 0, 255, 0, 0, 255, 255]);
+}
+
+#[test]
+fn p3_allows_any_whitespace_between_samples() {
+    let tileset = decode(b"P3\n1 1\n255\n  10\t\t20\r\n\n 30").unwrap();
+    assert_eq!(tileset.rgba, [10, 20, 30, 255]);
+}
+
+#[test]
+fn p3_scales_16_bit_maxval() {
+    let tileset = decode(b"P3 1 1 65535 65535 32768 0").unwrap();
+    assert_eq!(tileset.rgba, [255, 128, 0, 255]);
+}
+
+#[test]
+fn p6_scales_16_bit_maxval() {
+    let tileset = decode(b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00").unwrap();
+    assert_eq!(tileset.rgba, [255, 128, 0, 255]);
+}
+
+#[test]
+fn p3_rejects_counts_larger_than_the_data() {
+    assert!(matches!(
+        decode(b"P3 65535 65535 255 0 0 0"),
+        Err(TilesetError::Truncated)
+    ));
+}
+
+#[test]
+fn p3_rejects_bad_samples() {
+    assert!(matches!(
+        decode(b"P3 1 1 255 0 x 0"),
+        Err(TilesetError::Sample(sample)) if sample == "x"
+    ));
+}
+
+#[test]
+fn p6_reads_whitespace_valued_pixels() {
+    // Tab, newline, carriage return and space are pixel values here, not
+    // separators.
+    let tileset = decode(b"P6 2 1 255\n\t\n\r  \x0c").unwrap();
+    assert_eq!(tileset.rgba, [9, 10, 13, 255, 32, 32, 12, 255]);
+}
+
+/// Encodes `data` as a 2x1 PNG of the given color type.
+fn png(color: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(data).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+#[test]
+fn png_decodes_to_rgba() {
+    let rgba = [255, 0, 0, 128, 0, 0, 255, 255];
+    let tileset = decode(&png(png::ColorType::Rgba, &rgba)).unwrap();
+    assert_eq!((tileset.width, tileset.height), (2, 1));
+    assert_eq!(tileset.rgba, rgba);
+
+    let tileset = decode(&png(png::ColorType::Rgb, &[1, 2, 3, 4, 5, 6])).unwrap();
+    assert_eq!(tileset.rgba, [1, 2, 3, 255, 4, 5, 6, 255]);
+
+    let tileset = decode(&png(png::ColorType::GrayscaleAlpha, &[7, 8, 9, 10])).unwrap();
+    assert_eq!(tileset.rgba, [7, 7, 7, 8, 9, 9, 9, 10]);
+}
+
+#[test]
+fn png_rejects_corrupt_data() {
+    let mut bytes = png(png::ColorType::Rgb, &[1, 2, 3, 4, 5, 6]);
+    bytes.truncate(20);
+    assert!(matches!(decode(&bytes), Err(TilesetError::Png(_))));
+}
+
+#[test]
+fn non_square_atlases_and_glyphs() {
+    // 4x2 pixels.
+    let ppm = b"P3 4 2 255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0";
+    assert_eq!(Tileset::decode(ppm, [1, 1]).unwrap().grid, [4, 2]);
+    assert_eq!(Tileset::decode(ppm, [2, 1]).unwrap().grid, [2, 2]);
+    assert_eq!(Tileset::decode(ppm, [1, 2]).unwrap().grid, [4, 1]);
+    assert!(matches!(
+        Tileset::decode(ppm, [3, 1]),
+        Err(TilesetError::GlyphSize { .. })
+    ));
+}
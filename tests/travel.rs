@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use dung::travel::find_path;
+
+fn bounds() -> IRect {
+    IRect::new(0, 0, 9, 9)
+}
+
+#[test]
+fn paths_are_shortest() {
+    // A wall from (2, 0) to (2, 8) leaves one gap at the top.
+    let is_solid = |cell: IVec2| cell.x == 2 && cell.y < 9;
+
+    let path = find_path(IVec2::new(0, 0), IVec2::new(4, 0), bounds(), is_solid).unwrap();
+
+    // Up 9, across 4, down 9.
+    assert_eq!(path.len(), 22);
+    assert_eq!(path.last(), Some(&IVec2::new(4, 0)));
+    assert!(path.iter().all(|cell| !is_solid(*cell)));
+    let mut previous = IVec2::new(0, 0);
+    for cell in &path {
+        assert_eq!((*cell - previous).abs().element_sum(), 1);
+        previous = *cell;
+    }
+}
+
+#[test]
+fn paths_to_solid_targets_end_in_a_bump() {
+    let enemy = IVec2::new(3, 0);
+    let is_solid = |cell: IVec2| cell == enemy;
+
+    let path = find_path(IVec2::new(0, 0), enemy, bounds(), is_solid).unwrap();
+
+    assert_eq!(path, [IVec2::new(1, 0), IVec2::new(2, 0), enemy]);
+}
+
+#[test]
+fn unreachable_targets_have_no_path() {
+    // The target is walled in on all four sides.
+    let target = IVec2::new(5, 5);
+    let is_solid = |cell: IVec2| (cell - target).abs().element_sum() == 1;
+
+    assert_eq!(
+        find_path(IVec2::new(0, 0), target, bounds(), is_solid),
+        None
+    );
+    // Targets outside the map cannot be reached either.
+    assert_eq!(
+        find_path(IVec2::new(0, 0), IVec2::new(20, 0), bounds(), |_| false),
+        None
+    );
+}